@@ -7,8 +7,9 @@ use super::{
     avatar::AvatarConfig,
     client::{ClientConfig, ClientInner},
     local_db::DbConfig,
+    media::MediaConfig,
     temporary::{InnerTemporaryConfig, TemporaryConfig},
-    InnerAvatarConfig, InnerDbConfig,
+    InnerAvatarConfig, InnerDbConfig, InnerMediaConfig,
 };
 
 use crate::resource_directories::ResourceDirectories;
@@ -27,6 +28,8 @@ pub struct Config {
     #[serde(default = "Default::default")]
     avatar: AvatarConfig,
     #[serde(default = "Default::default")]
+    media: MediaConfig,
+    #[serde(default = "Default::default")]
     database: DbConfig,
     #[serde(default = "Default::default")]
     client: ClientConfig,
@@ -35,6 +38,7 @@ pub struct Config {
 pub struct InnerConfig {
     pub(crate) temporary: InnerTemporaryConfig,
     pub(crate) avatar: InnerAvatarConfig,
+    pub(crate) media: InnerMediaConfig,
     pub(crate) database: InnerDbConfig,
     pub(crate) client: ClientInner,
 }
@@ -44,6 +48,7 @@ impl Config {
         let root = root.with_set_path(self.resource_root);
         InnerConfig {
             avatar: self.avatar.into_inner(&root),
+            media: self.media.into_inner(&root),
             database: self.database.into_inner(&root),
             temporary: self.temporary.into_inner(),
             client: self.client.into(),
@@ -0,0 +1,102 @@
+use std::path::Path;
+
+use derivative::Derivative;
+use serde::{Deserialize, Serialize};
+
+use crate::resource_directories::ResourceDirectories;
+
+use super::{free_path_ref, static_leak};
+
+default_string! {
+    BaseDir => "media"
+    Images => "images"
+    Audios => "audios"
+    Videos => "videos"
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, Derivative)]
+#[derivative(Default)]
+pub struct MediaConfig {
+    #[derivative(Default(value = "BaseDir::get_default()"))]
+    #[serde(default = "BaseDir::get_default")]
+    #[serde(alias = "base")]
+    base_dir: String,
+    #[derivative(Default(value = "Images::get_default()"))]
+    #[serde(default = "Images::get_default")]
+    images: String,
+    #[derivative(Default(value = "Audios::get_default()"))]
+    #[serde(default = "Audios::get_default")]
+    audios: String,
+    #[derivative(Default(value = "Videos::get_default()"))]
+    #[serde(default = "Videos::get_default")]
+    videos: String,
+    /// Videos larger than this (in bytes) are never downloaded automatically.
+    #[derivative(Default(value = "default_video_auto_download_limit()"))]
+    #[serde(default = "default_video_auto_download_limit")]
+    #[serde(alias = "auto_download_limit")]
+    video_auto_download_limit: u64,
+}
+
+fn default_video_auto_download_limit() -> u64 {
+    8 * 1024 * 1024
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct InnerMediaConfig {
+    pub images: &'static Path,
+    pub audios: &'static Path,
+    pub videos: &'static Path,
+    pub video_auto_download_limit: u64,
+}
+
+impl MediaConfig {
+    pub(crate) fn into_inner(self, root: &ResourceDirectories) -> InnerMediaConfig {
+        let media = root.get_cache_home().join(&self.base_dir);
+
+        let images = static_leak(media.join(&self.images).into_boxed_path());
+        let audios = static_leak(media.join(&self.audios).into_boxed_path());
+        let videos = static_leak(media.join(&self.videos).into_boxed_path());
+
+        InnerMediaConfig {
+            images,
+            audios,
+            videos,
+            video_auto_download_limit: self.video_auto_download_limit,
+        }
+    }
+}
+
+impl Drop for InnerMediaConfig {
+    fn drop(&mut self) {
+        free_path_ref(self.images);
+        free_path_ref(self.audios);
+        free_path_ref(self.videos);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::{default_video_auto_download_limit, MediaConfig};
+
+    #[test]
+    fn test_not_full() {
+        let data = json! {
+            {
+                "base_dir": "media_cache",
+                "auto_download_limit": 1024
+            }
+        };
+
+        let media = serde_json::from_value::<MediaConfig>(data).unwrap();
+
+        assert_eq!(media.base_dir, "media_cache");
+        assert_eq!(media.videos, "videos");
+        assert_eq!(media.video_auto_download_limit, 1024);
+        assert_eq!(
+            MediaConfig::default().video_auto_download_limit,
+            default_video_auto_download_limit()
+        );
+    }
+}
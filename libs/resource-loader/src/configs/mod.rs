@@ -4,6 +4,7 @@ use std::path::Path;
 
 mod avatar;
 mod local_db;
+mod media;
 mod temporary;
 
 fn free_path_ref(path: &'static Path) {
@@ -19,3 +20,4 @@ fn static_leak<T: ?Sized>(boxed: Box<T>) -> &'static T {
 pub(crate) use avatar::InnerAvatarConfig;
 pub use config::{Config, InnerConfig};
 pub(crate) use local_db::InnerDbConfig;
+pub(crate) use media::InnerMediaConfig;
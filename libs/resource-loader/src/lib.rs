@@ -13,6 +13,7 @@ pub use ops::{
     avatar::{Group as AvatarGroup, User as AvatarUser},
     client::{Device, Protocol},
     database::SqlDataBase,
+    media::{Audio as MediaAudio, Image as MediaImage, Video as MediaVideo},
     temporary::{CaptchaQrCode, QrCodeLoginCode, TempDir},
     AsyncCreatePath, AsyncLoadResource, DirAction, GetPath, SyncCreatePath, SyncLoadResource,
 };
@@ -0,0 +1,42 @@
+use std::path::Path;
+
+use crate::{logger, static_data::load_cfg};
+
+use super::GetPath;
+
+pub struct Image;
+
+impl GetPath for Image {
+    fn get_path() -> &'static Path {
+        let cfg = load_cfg();
+        logger!(info "loading `Media Image` path");
+        cfg.media.images
+    }
+}
+
+pub struct Audio;
+
+impl GetPath for Audio {
+    fn get_path() -> &'static Path {
+        let cfg = load_cfg();
+        logger!(info "loading `Media Audio` path");
+        cfg.media.audios
+    }
+}
+
+pub struct Video;
+
+impl GetPath for Video {
+    fn get_path() -> &'static Path {
+        let cfg = load_cfg();
+        logger!(info "loading `Media Video` path");
+        cfg.media.videos
+    }
+}
+
+impl Video {
+    /// Videos larger than this (in bytes) should never be downloaded automatically.
+    pub fn auto_download_limit() -> u64 {
+        load_cfg().media.video_auto_download_limit
+    }
+}
@@ -1,6 +1,7 @@
 pub mod avatar;
 pub mod client;
 pub mod database;
+pub mod media;
 pub mod temporary;
 use std::path::Path;
 
@@ -7,15 +7,12 @@ use std::{
 
 use qrcode_png::{Color, QrCode};
 
-use ricq::{
-    client::{Connector, DefaultConnector},
-    ext::common::after_login,
-    Client, LoginUnknownStatus,
-};
-use tokio::task;
+use ricq::{ext::common::after_login, Client, LoginUnknownStatus};
 
 use crate::app::login::{service::token::LocalAccount, LoginPageMsg, REMEMBER_PWD};
 
+use crate::connection::{connect, supervise};
+use crate::db::account::restore_status;
 use crate::handler::{AppHandler, ACCOUNT, CLIENT};
 
 pub(super) mod handle_respond;
@@ -31,14 +28,7 @@ pub(crate) async fn init_client() -> io::Result<Arc<Client>> {
     ));
 
     // Connect to server
-    tokio::spawn({
-        let client = client.clone();
-        // 连接所有服务器，哪个最快用哪个，可以使用 TcpStream::connect 代替
-        let stream = DefaultConnector.connect(&client).await.unwrap();
-        async move { client.start(stream).await }
-    });
-
-    task::yield_now().await;
+    connect(&client).await?;
 
     Ok(client)
 }
@@ -46,17 +36,23 @@ pub(crate) async fn init_client() -> io::Result<Arc<Client>> {
 pub(crate) async fn finish_login(client: Arc<Client>, sender: &Sender<LoginPageMsg>) {
     let local = LocalAccount::new(&client).await;
 
-    use LoginPageMsg::LoginSuccessful;
-    if CLIENT.set(client.clone()).is_err() {
+    use LoginPageMsg::{LoginFailed, LoginSuccessful};
+    // After being logged out, the client is reused to log in again.
+    if !Arc::ptr_eq(CLIENT.get_or_init(|| client.clone()), &client) {
         panic!("falied to store client");
     };
-    if ACCOUNT.set(local.account).is_err() {
-        panic!("falied to store account");
+    if *ACCOUNT.get_or_init(|| local.account) != local.account {
+        sender.send(LoginFailed(
+            "Please restart Gtk QQ to log in with another account".into(),
+        ));
+        return;
     };
     if REMEMBER_PWD.load(Ordering::Relaxed) {
         local.save_account(sender);
     }
 
     after_login(&client).await;
+    restore_status(&client).await;
+    supervise(client.clone());
     sender.send(LoginSuccessful(client));
 }
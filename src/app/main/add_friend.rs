@@ -0,0 +1,334 @@
+use relm4::{adw, gtk, Component, ComponentParts, ComponentSender, WidgetPlus};
+
+use adw::{prelude::*, Avatar, HeaderBar, Window};
+use gtk::gdk_pixbuf::Pixbuf;
+use gtk::pango::EllipsizeMode;
+use gtk::{Align, Box, Button, Entry, InputPurpose, Label, Orientation, Picture};
+use tokio::task;
+
+use crate::db::friends;
+use crate::db::fs::{download_user_avatar_file, get_user_avatar_path};
+use crate::db::sql::get_friend;
+use crate::handler::{ACCOUNT, CLIENT};
+
+use super::profile::Summary;
+use super::{ChatKind, MainMsg};
+
+/// Look up a user by uin and ask them to become our friend.
+pub(crate) struct AddFriendModel {
+    window: Window,
+    /// The user whose card is shown, with what the server tells about them.
+    found: Option<(i64, Summary)>,
+    is_busy: bool,
+    /// Why nothing is shown, or why the request was not sent.
+    error: Option<String>,
+}
+
+impl AddFriendModel {
+    fn is_friend(&self) -> bool {
+        match &self.found {
+            Some((user_id, _)) => get_friend(*user_id).is_some(),
+            None => false,
+        }
+    }
+}
+
+pub(crate) struct Payload {
+    pub window: Window,
+}
+
+#[derive(Debug)]
+pub(crate) enum AddFriendMsg {
+    Search(String),
+    Found(i64, Summary),
+    SendRequest(String),
+    RequestSent,
+    SendMessage,
+    Failed(String),
+}
+
+async fn search(user_id: i64, sender: ComponentSender<AddFriendModel>) {
+    let client = CLIENT.get().unwrap();
+    match client.get_summary_info(user_id).await {
+        Ok(info) => sender.input(AddFriendMsg::Found(user_id, info.into())),
+        Err(err) => sender.input(AddFriendMsg::Failed(format!(
+            "No user is found with {}: {}",
+            user_id, err
+        ))),
+    }
+}
+
+async fn send_request(user_id: i64, message: String, sender: ComponentSender<AddFriendModel>) {
+    match friends::request_friend(user_id, message).await {
+        Ok(()) => sender.input(AddFriendMsg::RequestSent),
+        Err(err) => sender.input(AddFriendMsg::Failed(format!(
+            "Failed to send the friend request: {}",
+            err
+        ))),
+    }
+}
+
+fn load_avatar(avatar: &Avatar, user_id: i64) {
+    avatar.set_custom_image(None::<&gtk::gdk::Paintable>);
+    let avatar_path = get_user_avatar_path(user_id);
+    if avatar_path.exists() {
+        if let Ok(pixbuf) = Pixbuf::from_file_at_size(avatar_path, 64, 64) {
+            let image = Picture::for_pixbuf(&pixbuf);
+            if let Some(paintable) = image.paintable() {
+                avatar.set_custom_image(Some(&paintable));
+            }
+        }
+    } else {
+        task::spawn(download_user_avatar_file(user_id));
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct AddFriendWidgets {
+    uin_entry: Entry,
+    search_button: Button,
+    error_label: Label,
+    card: Box,
+    avatar: Avatar,
+    nickname_label: Label,
+    uin_label: Label,
+    signature_label: Label,
+    location_label: Label,
+    message_entry: Entry,
+    send_button: Button,
+    message_button: Button,
+}
+
+impl Component for AddFriendModel {
+    type Input = AddFriendMsg;
+    type Output = MainMsg;
+    type InitParams = Payload;
+    type Widgets = AddFriendWidgets;
+    type Root = Box;
+    type CommandOutput = ();
+
+    fn init_root() -> Self::Root {
+        Box::new(Orientation::Vertical, 0)
+    }
+
+    fn init(
+        params: Self::InitParams,
+        root: &Self::Root,
+        sender: &ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let model = AddFriendModel {
+            window: params.window,
+            found: None,
+            is_busy: false,
+            error: None,
+        };
+
+        relm4::view! {
+            header_bar = HeaderBar {
+                set_title_widget = Some(&Label) {
+                    set_label: "Add Friend",
+                },
+            }
+        }
+
+        relm4::view! {
+            content = Box {
+                set_orientation: Orientation::Vertical,
+                set_spacing: 12,
+                set_margin_all: 24,
+                Box {
+                    add_css_class: "linked",
+                    #[name = "uin_entry"]
+                    Entry {
+                        set_hexpand: true,
+                        set_placeholder_text: Some("QQ number"),
+                        set_input_purpose: InputPurpose::Digits,
+                        connect_activate[sender] => move |entry| {
+                            sender.input(AddFriendMsg::Search(entry.buffer().text()));
+                        },
+                    },
+                    #[name = "search_button"]
+                    Button {
+                        set_icon_name: "system-search-symbolic",
+                        set_tooltip_text: Some("Look up"),
+                        connect_clicked[sender, uin_entry] => move |_| {
+                            sender.input(AddFriendMsg::Search(uin_entry.buffer().text()));
+                        },
+                    },
+                },
+                #[name = "error_label"]
+                Label {
+                    set_wrap: true,
+                    set_visible: false,
+                    add_css_class: "error",
+                },
+                #[name = "card"]
+                Box {
+                    set_orientation: Orientation::Vertical,
+                    set_spacing: 8,
+                    set_visible: false,
+                    #[name = "avatar"]
+                    Avatar {
+                        set_size: 64,
+                        set_show_initials: true,
+                        set_halign: Align::Center,
+                    },
+                    #[name = "nickname_label"]
+                    Label {
+                        set_selectable: true,
+                        set_ellipsize: EllipsizeMode::End,
+                        add_css_class: "title-3",
+                    },
+                    #[name = "uin_label"]
+                    Label {
+                        set_selectable: true,
+                        set_css_classes: &["caption", "dim-label"],
+                    },
+                    #[name = "signature_label"]
+                    Label {
+                        set_wrap: true,
+                        set_justify: gtk::Justification::Center,
+                    },
+                    #[name = "location_label"]
+                    Label {
+                        set_css_classes: &["caption", "dim-label"],
+                    },
+                    #[name = "message_entry"]
+                    Entry {
+                        set_placeholder_text: Some("Verification message"),
+                        connect_activate[sender] => move |entry| {
+                            sender.input(AddFriendMsg::SendRequest(entry.buffer().text()));
+                        },
+                    },
+                    #[name = "send_button"]
+                    Button {
+                        set_label: "Send Request",
+                        set_halign: Align::Center,
+                        add_css_class: "suggested-action",
+                        connect_clicked[sender, message_entry] => move |_| {
+                            sender.input(AddFriendMsg::SendRequest(message_entry.buffer().text()));
+                        },
+                    },
+                    #[name = "message_button"]
+                    Button {
+                        set_label: "Message",
+                        set_halign: Align::Center,
+                        add_css_class: "suggested-action",
+                        connect_clicked[sender] => move |_| {
+                            sender.input(AddFriendMsg::SendMessage);
+                        },
+                    },
+                },
+            }
+        }
+
+        root.append(&header_bar);
+        root.append(&content);
+        uin_entry.grab_focus();
+
+        ComponentParts {
+            model,
+            widgets: AddFriendWidgets {
+                uin_entry,
+                search_button,
+                error_label,
+                card,
+                avatar,
+                nickname_label,
+                uin_label,
+                signature_label,
+                location_label,
+                message_entry,
+                send_button,
+                message_button,
+            },
+        }
+    }
+
+    fn update_with_view(
+        &mut self,
+        widgets: &mut Self::Widgets,
+        msg: Self::Input,
+        sender: &ComponentSender<Self>,
+    ) {
+        use AddFriendMsg::*;
+        match msg {
+            Search(text) => match text.trim().parse::<i64>() {
+                Ok(user_id) if Some(&user_id) == ACCOUNT.get() => {
+                    self.found = None;
+                    self.error = Some("This is your own QQ number.".into());
+                }
+                Ok(user_id) if !self.is_busy => {
+                    self.is_busy = true;
+                    self.error = None;
+                    task::spawn(search(user_id, sender.clone()));
+                }
+                Ok(_) => (),
+                Err(_) => {
+                    self.found = None;
+                    self.error = Some("Please enter a QQ number.".into());
+                }
+            },
+            Found(user_id, summary) => {
+                self.is_busy = false;
+                self.error = None;
+                load_avatar(&widgets.avatar, user_id);
+                widgets.message_entry.set_text("");
+                self.found = Some((user_id, summary));
+            }
+            SendRequest(message) => {
+                if let Some((user_id, _)) = &self.found {
+                    if !self.is_busy && !self.is_friend() {
+                        self.is_busy = true;
+                        task::spawn(send_request(*user_id, message, sender.clone()));
+                    }
+                }
+            }
+            RequestSent => {
+                if let Some((_, summary)) = &self.found {
+                    sender.output(MainMsg::PushToast(format!(
+                        "Sent a friend request to {}.",
+                        summary.nickname
+                    )));
+                }
+                self.window.close();
+            }
+            SendMessage => {
+                if let Some((user_id, _)) = &self.found {
+                    sender.output(MainMsg::SelectChatroom(*user_id, ChatKind::Friend));
+                    self.window.close();
+                }
+            }
+            Failed(err) => {
+                self.is_busy = false;
+                self.error = Some(err);
+            }
+        }
+
+        widgets.uin_entry.set_sensitive(!self.is_busy);
+        widgets.search_button.set_sensitive(!self.is_busy);
+        widgets.error_label.set_visible(self.error.is_some());
+        widgets
+            .error_label
+            .set_label(self.error.as_deref().unwrap_or_default());
+
+        widgets.card.set_visible(self.found.is_some());
+        if let Some((user_id, summary)) = &self.found {
+            let is_friend = self.is_friend();
+            widgets.avatar.set_text(Some(&summary.nickname));
+            widgets.nickname_label.set_label(&summary.nickname);
+            widgets.uin_label.set_label(&user_id.to_string());
+            widgets
+                .signature_label
+                .set_visible(!summary.signature.is_empty());
+            widgets.signature_label.set_label(&summary.signature);
+            widgets.location_label.set_visible(!summary.city.is_empty());
+            widgets.location_label.set_label(&summary.city);
+            widgets.message_entry.set_visible(!is_friend);
+            widgets.message_entry.set_sensitive(!self.is_busy);
+            widgets.send_button.set_visible(!is_friend);
+            widgets.send_button.set_sensitive(!self.is_busy);
+            widgets.message_button.set_visible(is_friend);
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::rc::{Rc, Weak};
+use std::time::Duration;
+
+use relm4::gtk;
+
+use gtk::glib::{self, clone, MainContext, WeakRef, PRIORITY_DEFAULT};
+use gtk::prelude::*;
+use gtk::{Align, Box, Button, Label};
+use tokio::task;
+
+use crate::utils::audio::AudioPlayer;
+use crate::utils::media::download_audio;
+
+const PLAY_ICON: &str = "media-playback-start-symbolic";
+const PAUSE_ICON: &str = "media-playback-pause-symbolic";
+
+fn format_duration(seconds: u64) -> String {
+    format!("{}\"", seconds)
+}
+
+struct AudioInner {
+    url: String,
+    /// In seconds, as told by the message until the audio is decoded.
+    duration: Cell<u64>,
+    player: RefCell<Option<AudioPlayer>>,
+    is_playing: Cell<bool>,
+    is_loading: Cell<bool>,
+    /// The button and the duration label of the bubble while it is shown.
+    view: RefCell<Option<(WeakRef<Button>, WeakRef<Label>)>>,
+}
+
+/// The playback of a voice message. It is kept by the message group, so it
+/// goes on while the row of the bubble is recycled and rebuilt.
+#[derive(Clone)]
+pub(super) struct AudioState(Rc<AudioInner>);
+
+impl fmt::Debug for AudioState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AudioState")
+            .field("url", &self.0.url)
+            .field("is_playing", &self.0.is_playing.get())
+            .finish()
+    }
+}
+
+impl AudioState {
+    pub(super) fn new(url: &str, duration: i32) -> Self {
+        AudioState(Rc::new(AudioInner {
+            url: url.to_string(),
+            duration: Cell::new(duration.max(0) as u64),
+            player: RefCell::new(None),
+            is_playing: Cell::new(false),
+            is_loading: Cell::new(false),
+            view: RefCell::new(None),
+        }))
+    }
+
+    fn render(&self) {
+        let inner = &self.0;
+        let view = inner.view.borrow();
+        let (button, duration_label) = match view.as_ref() {
+            Some((button, label)) => match (button.upgrade(), label.upgrade()) {
+                (Some(button), Some(label)) => (button, label),
+                _ => return,
+            },
+            None => return,
+        };
+        button.set_icon_name(if inner.is_playing.get() {
+            PAUSE_ICON
+        } else {
+            PLAY_ICON
+        });
+        button.set_sensitive(!inner.is_loading.get());
+        duration_label.set_label(&format_duration(inner.duration.get()));
+    }
+
+    fn set_playing(&self, is_playing: bool) {
+        self.0.is_playing.set(is_playing);
+        self.render();
+    }
+
+    /// Play or pause. The audio is only downloaded and decoded when it is
+    /// played for the first time.
+    fn toggle(&self) {
+        let inner = &self.0;
+        if let Some(player) = inner.player.borrow().as_ref() {
+            if inner.is_playing.get() {
+                player.pause();
+            } else {
+                player.play();
+            }
+            self.set_playing(!inner.is_playing.get());
+            return;
+        }
+
+        if inner.is_loading.replace(true) {
+            return;
+        }
+        self.render();
+
+        let (tx, rx) = MainContext::channel::<Option<(std::path::PathBuf, Option<Duration>)>>(
+            PRIORITY_DEFAULT,
+        );
+        let url = inner.url.clone();
+        task::spawn(async move {
+            match download_audio(url).await {
+                Ok(audio) => tx.send(Some(audio)).ok(),
+                Err(err) => {
+                    log::warn!("{}", err);
+                    tx.send(None).ok()
+                }
+            };
+        });
+
+        let state = self.clone();
+        rx.attach(None, move |audio| {
+            state.0.is_loading.set(false);
+            if let Some((path, duration)) = audio {
+                if let Some(duration) = duration {
+                    state.0.duration.set(duration.as_secs());
+                }
+                match AudioPlayer::new(&path) {
+                    Ok(player) => {
+                        // The player belongs to the state, which must not be
+                        // kept alive by its own handler.
+                        let weak: Weak<AudioInner> = Rc::downgrade(&state.0);
+                        player.connect_eos(move || {
+                            if let Some(inner) = weak.upgrade() {
+                                AudioState(inner).set_playing(false);
+                            }
+                        });
+                        player.play();
+                        state.0.player.replace(Some(player));
+                        state.0.is_playing.set(true);
+                    }
+                    Err(err) => log::warn!("{}", err),
+                }
+            }
+            state.render();
+            glib::Continue(false)
+        });
+    }
+}
+
+/// A voice message bubble, showing the playback of `state`.
+pub(super) fn audio_widget(state: &AudioState) -> Box {
+    relm4::view! {
+        root = Box {
+            set_spacing: 8,
+            #[name = "button"]
+            Button {
+                set_css_classes: &["circular", "flat"],
+            },
+            #[name = "duration_label"]
+            Label {
+                set_valign: Align::Center,
+            }
+        }
+    }
+
+    state
+        .0
+        .view
+        .replace(Some((button.downgrade(), duration_label.downgrade())));
+    state.render();
+
+    button.connect_clicked(clone!(@strong state => move |_| state.toggle()));
+
+    root
+}
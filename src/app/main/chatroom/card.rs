@@ -0,0 +1,73 @@
+use relm4::gtk;
+
+use gtk::pango::WrapMode;
+use gtk::prelude::*;
+use gtk::{Align, Box, Label, LinkButton, Orientation, Picture};
+
+use crate::utils::media::load_picture;
+use crate::utils::message::Card;
+
+pub(super) fn card_widget(card: &Card) -> Box {
+    relm4::view! {
+        root = Box {
+            set_orientation: Orientation::Vertical,
+            set_spacing: 4,
+            set_width_request: 240,
+            Label {
+                set_label: &card.title,
+                add_css_class: "heading",
+                set_xalign: 0.0,
+                set_wrap: true,
+                set_wrap_mode: WrapMode::WordChar,
+            }
+        }
+    }
+
+    if !card.description.is_empty() {
+        relm4::view! {
+            description = Label {
+                set_label: &card.description,
+                set_xalign: 0.0,
+                set_wrap: true,
+                set_wrap_mode: WrapMode::WordChar,
+                set_selectable: true,
+            }
+        }
+        root.append(&description);
+    }
+
+    if let Some(preview) = &card.preview {
+        relm4::view! {
+            picture = Picture {
+                set_height_request: 120,
+                set_can_shrink: true,
+            }
+        }
+        load_picture(&picture, preview.clone());
+        root.append(&picture);
+    }
+
+    if let Some(source) = &card.source {
+        relm4::view! {
+            source = Label {
+                set_label: source,
+                add_css_class: "caption",
+                set_xalign: 0.0,
+            }
+        }
+        root.append(&source);
+    }
+
+    if let Some(url) = &card.url {
+        relm4::view! {
+            link = LinkButton {
+                set_uri: url,
+                set_label: "Open",
+                set_halign: Align::Start,
+            }
+        }
+        root.append(&link);
+    }
+
+    root
+}
@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+
+use relm4::{gtk, WidgetPlus};
+
+use gtk::prelude::*;
+use gtk::{Align, Box, Label};
+
+use crate::utils::message::{Content, Message};
+
+use super::audio::{audio_widget, AudioState};
+use super::card::card_widget;
+use super::forward::forward_widget;
+use super::video::{video_widget, VideoState};
+
+/// The playback of the voice messages and the downloads of the videos of a
+/// message group, by url and by md5. They outlive the bubbles, which are
+/// rebuilt whenever the row of the group is recycled.
+#[derive(Debug, Default)]
+pub(crate) struct MediaStates {
+    audios: HashMap<String, AudioState>,
+    videos: HashMap<String, VideoState>,
+}
+
+fn append_content(container: &Box, content: Content, media: &mut MediaStates) {
+    match content {
+        Content::Text(text) => {
+            let label = Label::builder().label(&text).selectable(true).build();
+            container.append(&label)
+        }
+        Content::Image {
+            url: _,
+            filename: _,
+        } => {
+            let label = Label::new(Some("[图片]"));
+            container.append(&label)
+        }
+        Content::Card(card) => container.append(&card_widget(&card)),
+        Content::Audio { url, duration } => {
+            let state = media
+                .audios
+                .entry(url.clone())
+                .or_insert_with(|| AudioState::new(&url, duration));
+            container.append(&audio_widget(state))
+        }
+        Content::Video(video) => {
+            let state = media
+                .videos
+                .entry(video.md5.clone())
+                .or_insert_with(|| VideoState::new(&video));
+            container.append(&video_widget(state))
+        }
+        Content::Forward(forward) => container.append(&forward_widget(&forward)),
+    }
+}
+
+/// The bubble of a single message.
+pub(super) fn message_bubble(message: &Message, alignment: Align, media: &mut MediaStates) -> Box {
+    relm4::view! {
+        message_box = Box {
+            set_css_classes: &["card", "message-box"],
+            set_halign: alignment,
+            set_margin_all: 2,
+            #[name = "inner_message_box"]
+            Box {
+                set_css_classes: &["inner-message-box"],
+                set_margin_all: 8,
+            }
+        }
+    }
+
+    for content in message.contents.clone() {
+        append_content(&inner_message_box, content, media);
+    }
+
+    message_box
+}
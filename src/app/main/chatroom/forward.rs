@@ -0,0 +1,167 @@
+use relm4::{adw, gtk};
+
+use adw::{prelude::*, HeaderBar, Window};
+use gtk::glib::{self, clone, MainContext, PRIORITY_DEFAULT};
+use gtk::pango::EllipsizeMode;
+use gtk::{Align, Box, Button, Label, Orientation, ScrolledWindow, Separator, Spinner};
+use ricq::structs::ForwardMessage;
+use tokio::task;
+
+use crate::global::WINDOW;
+use crate::handler::CLIENT;
+use crate::utils::message::{get_contents_from, Content, Forward, ForwardSource, Message};
+
+use super::content::{message_bubble, MediaStates};
+
+/// Render the messages of a bundle. Nested bundles are rendered as forward
+/// cards again, so they can be opened recursively.
+fn render_nodes(container: &Box, nodes: &[ForwardMessage]) {
+    let mut media = MediaStates::default();
+    for node in nodes {
+        let message = match node {
+            ForwardMessage::Message(node) => Message {
+                sender_id: node.sender_id,
+                sender_name: node.sender_name.clone(),
+                contents: get_contents_from(&node.elements),
+                elements: node.elements.clone(),
+            },
+            ForwardMessage::Forward(node) => Message {
+                sender_id: node.sender_id,
+                sender_name: node.sender_name.clone(),
+                contents: vec![Content::Forward(node.clone().into())],
+                elements: Default::default(),
+            },
+        };
+
+        relm4::view! {
+            row = Box {
+                set_orientation: Orientation::Vertical,
+                set_spacing: 4,
+                set_margin_bottom: 8,
+                Label {
+                    set_label: &message.sender_name,
+                    set_css_classes: &["caption"],
+                    set_halign: Align::Start,
+                }
+            }
+        }
+        row.append(&message_bubble(&message, Align::Start, &mut media));
+        container.append(&row);
+    }
+}
+
+fn open_forward_viewer(forward: &Forward) {
+    let window = Window::builder()
+        .transient_for(&WINDOW.get().unwrap().window)
+        .default_width(480)
+        .default_height(600)
+        .build();
+
+    relm4::view! {
+        content = Box {
+            set_orientation: Orientation::Vertical,
+            HeaderBar {
+                set_title_widget = Some(&Label) {
+                    set_label: &forward.title(),
+                    add_css_class: "title",
+                }
+            },
+            ScrolledWindow {
+                set_vexpand: true,
+                set_hexpand: true,
+                set_child: messages_box = Some(&Box) {
+                    set_orientation: Orientation::Vertical,
+                    set_css_classes: &["chatroom-box"],
+                }
+            }
+        }
+    }
+
+    match &forward.source {
+        ForwardSource::Nodes(nodes) => render_nodes(&messages_box, nodes),
+        ForwardSource::ResId(res_id) => {
+            let spinner = Spinner::builder().spinning(true).build();
+            messages_box.append(&spinner);
+
+            let (tx, rx) = MainContext::channel(PRIORITY_DEFAULT);
+            task::spawn(clone!(@strong res_id => async move {
+                let client = CLIENT.get().unwrap();
+                let res = client
+                    .download_msgs(res_id)
+                    .await
+                    .map_err(|err| err.to_string());
+                tx.send(res).ok();
+            }));
+
+            rx.attach(
+                None,
+                clone!(@strong messages_box => move |res| {
+                    messages_box.remove(&spinner);
+                    match res {
+                        Ok(nodes) => render_nodes(&messages_box, &nodes),
+                        Err(err) => {
+                            let label = Label::new(Some(&format!("Failed to load the chat history: {}", err)));
+                            messages_box.append(&label);
+                        }
+                    }
+                    glib::Continue(false)
+                }),
+            );
+        }
+    }
+
+    window.set_content(Some(&content));
+    window.present();
+}
+
+/// The compact card of a forwarded bundle, which opens a read-only chat view.
+pub(super) fn forward_widget(forward: &Forward) -> Box {
+    relm4::view! {
+        root = Box {
+            set_orientation: Orientation::Vertical,
+            set_spacing: 4,
+            set_width_request: 240,
+            Label {
+                set_label: &forward.title(),
+                add_css_class: "heading",
+                set_xalign: 0.0,
+                set_ellipsize: EllipsizeMode::End,
+            },
+            #[name = "preview_box"]
+            Box {
+                set_orientation: Orientation::Vertical,
+            },
+            Separator {},
+            Box {
+                Label {
+                    set_label: &forward.summary,
+                    add_css_class: "caption",
+                    set_hexpand: true,
+                    set_xalign: 0.0,
+                },
+                #[name = "button"]
+                Button {
+                    set_label: "View",
+                    add_css_class: "flat",
+                }
+            }
+        }
+    }
+
+    for line in forward.preview.iter() {
+        relm4::view! {
+            label = Label {
+                set_label: line,
+                add_css_class: "caption",
+                set_xalign: 0.0,
+                set_ellipsize: EllipsizeMode::End,
+            }
+        }
+        preview_box.append(&label);
+    }
+
+    let forward = forward.clone();
+    button.connect_clicked(move |_| open_forward_viewer(&forward));
+
+    root
+}
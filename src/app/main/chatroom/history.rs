@@ -0,0 +1,159 @@
+use relm4::Sender;
+use ricq::structs::{FriendAudio, GroupAudio};
+
+use crate::db::sql::{get_member_name, get_messages_before, save_message, MessageRecord};
+use crate::handler::CLIENT;
+use crate::utils::message::{
+    decode_elements, encode_elements, get_contents_from, resolve_video_urls, Content, Message,
+};
+
+use super::{ChatKind, ChatroomMsg};
+
+/// How many messages are loaded each time we scroll to the top.
+const PAGE_SIZE: usize = 30;
+
+/// A page of older messages in chronological order.
+#[derive(Debug)]
+pub(crate) struct HistoryPage {
+    pub messages: Vec<Message>,
+    /// The `(time, seq)` of the oldest message, or `None` if there is no more history.
+    pub oldest: Option<(i32, i32)>,
+}
+
+async fn to_message(record: MessageRecord) -> Option<Message> {
+    let client = CLIENT.get().unwrap();
+    let (elements, ptt) = decode_elements(&record.elements)?;
+    let contents = match ptt {
+        Some(ptt) => {
+            let duration = ptt.time.unwrap_or_default();
+            let url = if record.kind.is_group() {
+                client
+                    .get_group_audio_url(record.chat_id, GroupAudio(ptt))
+                    .await
+            } else {
+                client
+                    .get_friend_audio_url(record.sender_id, FriendAudio(ptt))
+                    .await
+            };
+            match url {
+                Ok(url) => vec![Content::Audio { url, duration }],
+                Err(_) => vec![Content::Text("[语音]".to_string())],
+            }
+        }
+        None => {
+            let mut contents = get_contents_from(&elements);
+            resolve_video_urls(
+                client,
+                record.chat_id,
+                record.kind.is_group(),
+                &mut contents,
+            )
+            .await;
+            contents
+        }
+    };
+
+    // The name saved with the record may be outdated, or empty.
+    let sender_name = if record.kind.is_group() {
+        get_member_name(record.chat_id, record.sender_id, &record.sender_name)
+    } else {
+        record.sender_name
+    };
+
+    Some(Message {
+        sender_id: record.sender_id,
+        sender_name,
+        contents,
+        elements,
+    })
+}
+
+/// Fetch the messages before `before` from the server, and save them to the
+/// local history.
+async fn fetch_group_history(
+    group_code: i64,
+    before: Option<(i32, i32)>,
+) -> Result<Vec<MessageRecord>, String> {
+    let client = CLIENT.get().unwrap();
+    let to_seq = match before {
+        Some((_, seq)) => seq - 1,
+        None => match client.get_group_info(group_code).await {
+            Ok(Some(info)) => info.last_msg_seq as i32,
+            Ok(None) => return Err(format!("Group {} is not found", group_code)),
+            Err(err) => return Err(err.to_string()),
+        },
+    };
+    if to_seq < 1 {
+        return Ok(Vec::new());
+    }
+    let from_seq = (to_seq - PAGE_SIZE as i32 + 1).max(1);
+
+    let messages = client
+        .get_group_msgs(group_code, from_seq, to_seq)
+        .await
+        .map_err(|err| err.to_string())?;
+
+    let mut records: Vec<MessageRecord> = messages
+        .into_iter()
+        .map(|message| MessageRecord {
+            chat_id: group_code,
+            kind: ChatKind::Group,
+            seq: message.seqs.first().copied().unwrap_or_default(),
+            time: message.time,
+            sender_id: message.from_uin,
+            sender_name: message.group_card,
+            elements: encode_elements(&message.elements, None),
+        })
+        .collect();
+    records.sort_by_key(|record| (record.time, record.seq));
+
+    for record in records.iter() {
+        if let Err(err) = save_message(record) {
+            log::warn!("Failed to save message to history: {}", err);
+        }
+    }
+
+    Ok(records)
+}
+
+/// Load the messages before `before` from the local history, or from the
+/// server if there is no more local history. Only the messages saved up to
+/// `max_rowid` are loaded locally, since the later ones are pushed to the
+/// chatroom as they arrive.
+pub(super) async fn load_history(
+    chat_id: i64,
+    kind: ChatKind,
+    before: Option<(i32, i32)>,
+    max_rowid: i64,
+    input: Sender<ChatroomMsg>,
+) {
+    let mut records = get_messages_before(chat_id, kind, before, max_rowid, PAGE_SIZE)
+        .unwrap_or_else(|err| {
+            log::warn!("Failed to load history: {}", err);
+            Vec::new()
+        });
+    // ricq does not provide the roaming messages of friends, so the history
+    // of a friend ends with the local one.
+    if records.is_empty() && kind.is_group() {
+        records = match fetch_group_history(chat_id, before).await {
+            Ok(records) => records,
+            Err(err) => {
+                input.send(ChatroomMsg::HistoryFailed(format!(
+                    "Failed to fetch group history: {}",
+                    err
+                )));
+                return;
+            }
+        };
+    }
+
+    let oldest = records.first().map(|record| (record.time, record.seq));
+    let mut messages = Vec::new();
+    for record in records {
+        if let Some(message) = to_message(record).await {
+            messages.push(message);
+        }
+    }
+
+    input.send(ChatroomMsg::HistoryLoaded(HistoryPage { messages, oldest }));
+}
@@ -1,69 +1,138 @@
-use relm4::factory::{DynamicIndex, FactoryComponent};
-use relm4::{adw, gtk, Sender, WidgetPlus};
+use relm4::actions::{RelmAction, RelmActionGroup};
+use relm4::{adw, gtk, Sender};
 
 use adw::{prelude::*, Avatar};
+use gtk::gdk::Rectangle;
 use gtk::gdk_pixbuf::Pixbuf;
-use gtk::{Align, Box, Label, Orientation, Picture, Widget};
+use gtk::glib::clone;
+use gtk::{Align, Box, GestureClick, Label, Orientation, Picture, PopoverMenu};
 use tokio::task;
 
 use crate::db::fs::{download_user_avatar_file, get_user_avatar_path};
 use crate::handler::ACCOUNT;
-use crate::utils::message::{Content, Message};
+use crate::utils::message::Message;
 
+use super::content::{message_bubble, MediaStates};
 use super::ChatroomMsg;
 
-#[derive(Debug, Clone)]
+relm4::new_action_group!(MessageActionGroup, "message");
+relm4::new_stateless_action!(ForwardAction, MessageActionGroup, "forward");
+relm4::new_stateless_action!(AddToForwardAction, MessageActionGroup, "add-to-forward");
+
+/// Show a context menu with the forwarding actions when `bubble` is right clicked.
+fn attach_context_menu(bubble: &Box, message: &Message, output: &Sender<ChatroomMsg>) {
+    let forward: RelmAction<ForwardAction> =
+        RelmAction::new_stateless(clone!(@strong output, @strong message => move |_| {
+            output.send(ChatroomMsg::ForwardMessages(vec![message.clone()]));
+        }));
+    let add_to_forward: RelmAction<AddToForwardAction> =
+        RelmAction::new_stateless(clone!(@strong output, @strong message => move |_| {
+            output.send(ChatroomMsg::AddToForwardSelection(message.clone()));
+        }));
+    let actions: RelmActionGroup<MessageActionGroup> = RelmActionGroup::new();
+    actions.add_action(forward);
+    actions.add_action(add_to_forward);
+    bubble.insert_action_group("message", Some(&actions.into_action_group()));
+
+    relm4::menu! {
+        message_menu: {
+            "Forward…" => ForwardAction,
+            "Add to Merged Forward" => AddToForwardAction
+        }
+    }
+
+    let popover = PopoverMenu::from_model(Some(&message_menu));
+    popover.set_has_arrow(false);
+    popover.set_parent(bubble);
+    // The bubble is dropped when its row is unbound, but it does not
+    // unparent the popover by itself.
+    bubble.connect_destroy(clone!(@weak popover => move |_| popover.unparent()));
+
+    let gesture = GestureClick::builder().button(3).build();
+    gesture.connect_pressed(clone!(@weak popover => move |_, _, x, y| {
+        popover.set_pointing_to(Some(&Rectangle::new(x as i32, y as i32, 1, 1)));
+        popover.popup();
+    }));
+    bubble.add_controller(&gesture);
+}
+
+/// A run of consecutive messages from the same sender, shown as one row of
+/// the chatroom's `ListView`.
+#[derive(Debug)]
 pub(crate) struct MessageGroup {
     pub sender_id: i64,
     pub sender_name: String,
     pub messages: Vec<Message>,
+    /// The voice messages being played and the videos being downloaded,
+    /// which go on while the row is recycled.
+    media: MediaStates,
+    /// The box holding the bubbles while the row is bound to a list item,
+    /// so that new messages can be appended without rebuilding the row.
+    messages_box: Option<Box>,
+    /// The avatar and the name label while the row is bound, for renaming.
+    sender_widgets: Option<(Avatar, Label)>,
 }
 
-impl FactoryComponent<Box, ChatroomMsg> for MessageGroup {
-    type Widgets = ();
-    type Input = ChatroomMsg;
-    type Root = Box;
-    type Command = ();
-    type CommandOutput = ();
-    type InitParams = MessageGroup;
-    type Output = ();
-
-    fn init_model(
-        message: Self::InitParams,
-        _index: &DynamicIndex,
-        _input: &Sender<Self::Input>,
-        _output: &Sender<Self::Output>,
-    ) -> MessageGroup {
-        message
+impl MessageGroup {
+    pub(crate) fn new(message: Message) -> Self {
+        MessageGroup {
+            sender_id: message.sender_id,
+            sender_name: message.sender_name.clone(),
+            messages: vec![message],
+            media: MediaStates::default(),
+            messages_box: None,
+            sender_widgets: None,
+        }
     }
 
-    fn init_root(&self) -> Self::Root {
-        let root_box = Box::builder()
-            .orientation(Orientation::Horizontal)
-            .spacing(8)
-            .margin_bottom(8)
-            .build();
-
-        if &self.sender_id == ACCOUNT.get().unwrap() {
-            root_box.set_halign(Align::End)
+    pub(crate) fn rename(&mut self, name: &str) {
+        self.sender_name = name.to_string();
+        for message in self.messages.iter_mut() {
+            message.sender_name = name.to_string();
+        }
+        if let Some((avatar, username_label)) = &self.sender_widgets {
+            avatar.set_text(Some(name));
+            username_label.set_label(name);
         }
-
-        root_box
     }
 
-    fn init_widgets(
-        &mut self,
-        _index: &DynamicIndex,
-        root: &Self::Root,
-        _returned_widget: &Widget,
-        _input: &Sender<Self::Input>,
-        _output: &Sender<Self::Output>,
-    ) -> Self::Widgets {
-        let message_alignment = if &self.sender_id == ACCOUNT.get().unwrap() {
+    fn alignment(&self) -> Align {
+        if &self.sender_id == ACCOUNT.get().unwrap() {
             Align::End
         } else {
             Align::Start
-        };
+        }
+    }
+
+    /// Append `message` to the group, and to its row if it is realized.
+    pub(crate) fn push(&mut self, message: Message, output: &Sender<ChatroomMsg>) {
+        let alignment = self.alignment();
+        if let Some(messages_box) = &self.messages_box {
+            let message_box = message_bubble(&message, alignment, &mut self.media);
+            attach_context_menu(&message_box, &message, output);
+            messages_box.append(&message_box);
+        }
+        self.messages.push(message);
+    }
+
+    /// Insert older `messages` before the ones in the group.
+    pub(crate) fn prepend(&mut self, messages: Vec<Message>, output: &Sender<ChatroomMsg>) {
+        let alignment = self.alignment();
+        if let Some(messages_box) = &self.messages_box {
+            for message in messages.iter().rev() {
+                let message_box = message_bubble(message, alignment, &mut self.media);
+                attach_context_menu(&message_box, message, output);
+                messages_box.prepend(&message_box);
+            }
+        }
+        self.messages.splice(0..0, messages);
+    }
+
+    /// Build the widgets of the row into `root`, which is the child of the
+    /// list item the group is bound to.
+    pub(crate) fn bind(&mut self, root: &Box, output: &Sender<ChatroomMsg>) {
+        let message_alignment = self.alignment();
+        root.set_halign(message_alignment);
 
         relm4::view! {
             avatar_box = Box {
@@ -77,6 +146,13 @@ impl FactoryComponent<Box, ChatroomMsg> for MessageGroup {
             }
         }
 
+        let gesture = GestureClick::new();
+        let sender_id = self.sender_id;
+        gesture.connect_released(clone!(@strong output => move |_, _, _, _| {
+            output.send(ChatroomMsg::ShowProfile(sender_id));
+        }));
+        avatar.add_controller(&gesture);
+
         let avatar_path = get_user_avatar_path(self.sender_id);
         if avatar_path.exists() {
             if let Ok(pixbuf) = Pixbuf::from_file_at_size(avatar_path, 32, 32) {
@@ -106,45 +182,27 @@ impl FactoryComponent<Box, ChatroomMsg> for MessageGroup {
         }
 
         for message in self.messages.iter() {
-            // let label = message.text();
-            relm4::view! {
-                message_box = Box {
-                    set_css_classes: &["card", "message-box"],
-                    set_halign: message_alignment,
-                    set_margin_all: 2,
-                    #[name = "inner_message_box"]
-                    Box {
-                        set_css_classes: &["inner-message-box"],
-                        set_margin_all: 8,
-                    }
-                }
-            }
-            for content in message.contents.clone() {
-                match content {
-                    Content::Text(text) => {
-                        let label = Label::builder().label(&text).selectable(true).build();
-                        inner_message_box.append(&label)
-                    }
-                    Content::Image {
-                        url: _,
-                        filename: _,
-                    } => {
-                        let label = Label::new(Some("[图片]"));
-                        inner_message_box.append(&label)
-                    }
-                }
-            }
+            let message_box = message_bubble(message, message_alignment, &mut self.media);
+            attach_context_menu(&message_box, message, output);
             messages_box.append(&message_box);
         }
 
-        if &self.sender_id == ACCOUNT.get().unwrap() {
-            username_label.set_halign(Align::End);
+        username_label.set_halign(message_alignment);
+        if message_alignment == Align::End {
             root.append(&main_box);
             root.append(&avatar_box);
         } else {
-            username_label.set_halign(Align::Start);
             root.append(&avatar_box);
             root.append(&main_box);
         }
+
+        self.messages_box = Some(messages_box);
+        self.sender_widgets = Some((avatar, username_label));
+    }
+
+    /// Forget the widgets of the row once its list item gets recycled.
+    pub(crate) fn unbind(&mut self) {
+        self.messages_box = None;
+        self.sender_widgets = None;
     }
 }
@@ -1,99 +1,415 @@
+mod audio;
+mod card;
+mod content;
+mod forward;
+mod history;
 mod message_group;
+mod row;
+mod video;
 
-use std::collections::VecDeque;
+use std::cell::Cell;
+use std::rc::Rc;
 
-use relm4::factory::{DynamicIndex, FactoryComponent, FactoryVecDeque};
+use relm4::factory::{DynamicIndex, FactoryComponent};
 use relm4::{adw, gtk, Sender, WidgetPlus};
 
 use adw::prelude::*;
-use gtk::{Box, Button, Entry, Orientation, ScrolledWindow, Stack, StackPage};
+use gtk::glib::{self, BoxedAnyObject};
+use gtk::{
+    gio, Adjustment, Align, Box, Button, Entry, Label, ListItem, ListView, NoSelection,
+    Orientation, Overlay, Revealer, ScrolledWindow, SignalListItemFactory, Stack, StackPage,
+};
 use ricq::msg::{elem, MessageChain};
 use tokio::task;
 
-use crate::db::sql::get_friend_remark;
-use crate::handler::{ACCOUNT, CLIENT};
-use crate::utils::message::{Content, Message};
+use crate::connection::{connection_state, ConnectionState};
+use crate::db::sql::{
+    add_pending_message, delete_pending_message, get_friend_remark, get_group_left,
+    get_last_message_rowid, get_pending_messages, has_group_members, refresh_group_members,
+    ChatKind,
+};
+use crate::handler::{save_sent_message, ACCOUNT, CLIENT};
+use crate::utils::message::{describe_group_left, Content, Message};
 
 use super::MainMsg;
+use history::{load_history, HistoryPage};
 use message_group::MessageGroup;
+use row::{PendingMessage, Row};
+
+/// The name of the chatroom of `account` in the chatroom stack.
+pub(crate) fn chatroom_name(account: i64, kind: ChatKind) -> String {
+    match kind {
+        ChatKind::Friend => format!("{} friend", account),
+        ChatKind::Group => format!("{} group", account),
+        ChatKind::Temp { group_id } => format!("{} temp {}", account, group_id),
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum HistoryState {
+    Idle,
+    Loading,
+    /// There are no older messages, either locally or on the server.
+    Exhausted,
+}
 
 #[derive(Debug)]
 pub(crate) struct Chatroom {
     pub account: i64,
-    /// 群组/好友
-    pub is_group: bool,
-    pub messages: FactoryVecDeque<Box, MessageGroup, ChatroomMsg>,
+    pub kind: ChatKind,
+    /// The `Row`s of the chatroom, wrapped in `BoxedAnyObject`s.
+    pub messages: gio::ListStore,
+    list_view: ListView,
+    scrolled_window: ScrolledWindow,
+    jump_button: Button,
+    input: Sender<ChatroomMsg>,
     input_box: Box,
+    /// The `(time, seq)` of the oldest loaded message.
+    oldest: Option<(i32, i32)>,
+    /// The last message saved before the chatroom was opened. The later
+    /// ones are pushed as they arrive, so they are not loaded as history.
+    history_end: i64,
+    history_state: HistoryState,
+    /// Messages received while the chatroom is scrolled away from the bottom.
+    unread_count: usize,
+    /// Whether to keep the view at the bottom as the messages grow, which is
+    /// the case while the user stays at the bottom.
+    stick_to_bottom: Rc<Cell<bool>>,
+    /// The messages to be bundled as a merged forward.
+    forward_selection: Vec<Message>,
+    selection_bar: Revealer,
+    selection_label: Label,
+    online: bool,
+    offline_banner: Revealer,
+    /// The messages which are not sent yet, shown in the same order by the
+    /// last rows. The queued ones are sent once reconnected.
+    pending: Vec<PendingMessage>,
 }
 
 impl Chatroom {
     pub(crate) fn push_message(&mut self, message: Message) {
-        if self.messages.is_empty() {
-            self.messages.push_back(MessageGroup {
-                sender_id: message.sender_id,
-                sender_name: message.sender_name.clone(),
-                messages: vec![message],
-            });
-        } else {
-            let mut last_message_group = self.messages.pop_back().unwrap();
-            if last_message_group.sender_id == message.sender_id {
-                last_message_group.messages.push(message);
-                self.messages.push_back(last_message_group);
-            } else {
-                self.messages.push_back(last_message_group);
-                self.messages.push_back(MessageGroup {
-                    sender_id: message.sender_id,
-                    sender_name: message.sender_name.clone(),
-                    messages: vec![message],
-                });
+        if &message.sender_id == ACCOUNT.get().unwrap() {
+            // Always follow the messages sent by ourselves.
+            self.stick_to_bottom.set(true);
+            self.scroll_to_bottom();
+        } else if !self.stick_to_bottom.get() {
+            self.unread_count += 1;
+            self.update_jump_button();
+        }
+
+        let end = self.pending_start();
+        if let Some(last) = end
+            .checked_sub(1)
+            .and_then(|index| self.messages.item(index))
+        {
+            let last = last.downcast::<BoxedAnyObject>().unwrap();
+            let mut last_row = last.borrow_mut::<Row>();
+            if let Row::Messages(last_message_group) = &mut *last_row {
+                if last_message_group.sender_id == message.sender_id {
+                    last_message_group.push(message, &self.input);
+                    return;
+                }
+            }
+        }
+
+        self.messages.insert(
+            end,
+            &BoxedAnyObject::new(Row::Messages(MessageGroup::new(message))),
+        );
+    }
+
+    /// Show the new name of `sender_id` in the loaded messages.
+    pub(crate) fn rename_sender(&mut self, sender_id: i64, name: &str) {
+        for index in 0..self.messages.n_items() {
+            let row = self.messages.item(index).unwrap();
+            let row = row.downcast::<BoxedAnyObject>().unwrap();
+            let mut row = row.borrow_mut::<Row>();
+            if let Row::Messages(group) = &mut *row {
+                if group.sender_id == sender_id {
+                    group.rename(name);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn push_notice(&mut self, notice: String) {
+        self.messages.insert(
+            self.pending_start(),
+            &BoxedAnyObject::new(Row::Notice(notice)),
+        );
+    }
+
+    /// The index of the first pending row, which follow the other rows.
+    fn pending_start(&self) -> u32 {
+        self.messages.n_items() - self.pending.len() as u32
+    }
+
+    fn push_pending(&mut self, pending: PendingMessage) {
+        self.messages
+            .append(&BoxedAnyObject::new(Row::Pending(pending.clone())));
+        self.pending.push(pending);
+    }
+
+    /// Rebuild the row of the pending message at `index` of `pending`.
+    fn update_pending(&mut self, index: usize) {
+        let position = self.pending_start() + index as u32;
+        let row: glib::Object =
+            BoxedAnyObject::new(Row::Pending(self.pending[index].clone())).upcast();
+        self.messages.splice(position, 1, &[row]);
+    }
+
+    fn remove_pending(&mut self, id: i64) {
+        if let Some(index) = self.pending.iter().position(|pending| pending.id == id) {
+            self.messages.remove(self.pending_start() + index as u32);
+            self.pending.remove(index);
+        }
+        if let Err(err) = delete_pending_message(id) {
+            log::warn!("Failed to delete the pending message: {}", err);
+        }
+    }
+
+    fn send_pending(&mut self, index: usize, output: &Sender<MainMsg>) {
+        self.pending[index].queued = false;
+        self.update_pending(index);
+        let PendingMessage { id, content, .. } = self.pending[index].clone();
+        task::spawn(send_message(
+            self.account,
+            self.kind,
+            id,
+            content,
+            self.input.clone(),
+            output.clone(),
+        ));
+    }
+
+    /// Stop sending messages to a group which is disbanded or left.
+    pub(crate) fn set_read_only(&mut self) {
+        self.input_box.set_sensitive(false);
+    }
+
+    /// Show the offline banner while disconnected, and send the queued
+    /// messages once reconnected.
+    pub(crate) fn set_online(&mut self, online: bool) {
+        self.online = online;
+        self.offline_banner.set_reveal_child(!online);
+        if online {
+            self.input.send(ChatroomMsg::SendQueued);
+        }
+    }
+
+    /// Insert a page of older messages before the loaded ones, keeping the
+    /// distance between the viewport and the bottom unchanged.
+    fn prepend_history(&mut self, messages: Vec<Message>) {
+        let mut groups: Vec<MessageGroup> = Vec::new();
+        for message in messages {
+            match groups.last_mut() {
+                Some(group) if group.sender_id == message.sender_id => group.messages.push(message),
+                _ => groups.push(MessageGroup::new(message)),
+            }
+        }
+
+        if let Some(first) = self.messages.item(0) {
+            let first = first.downcast::<BoxedAnyObject>().unwrap();
+            let mut first_row = first.borrow_mut::<Row>();
+            if let Row::Messages(first_message_group) = &mut *first_row {
+                let last_sender = groups.last().map(|group| group.sender_id);
+                if last_sender == Some(first_message_group.sender_id) {
+                    let last_group = groups.pop().unwrap();
+                    first_message_group.prepend(last_group.messages, &self.input);
+                }
             }
         }
 
-        self.messages.render_changes();
+        let adjustment = self.scrolled_window.vadjustment();
+        let distance_from_bottom = adjustment.upper() - adjustment.value();
+        let groups: Vec<glib::Object> = groups
+            .into_iter()
+            .map(|group| BoxedAnyObject::new(Row::Messages(group)).upcast())
+            .collect();
+        self.messages.splice(0, 0, &groups);
+
+        let input = self.input.clone();
+        let need_more = self.history_state == HistoryState::Idle;
+        glib::idle_add_local_once(move || {
+            adjustment.set_value(adjustment.upper() - distance_from_bottom);
+            // Keep loading until the messages fill the viewport, since we can
+            // not scroll to the top otherwise.
+            if need_more && adjustment.upper() <= adjustment.page_size() {
+                input.send(ChatroomMsg::LoadHistory);
+            }
+        });
+    }
+
+    fn is_far_from_bottom(&self) -> bool {
+        let adjustment = self.scrolled_window.vadjustment();
+        adjustment.upper() - adjustment.value() - adjustment.page_size() > adjustment.page_size()
+    }
+
+    /// The button shows "Jump to latest" when we are far from the bottom, or
+    /// the number of new messages since we scrolled away from the bottom.
+    fn update_jump_button(&mut self) {
+        if is_at_bottom(&self.scrolled_window.vadjustment()) {
+            self.unread_count = 0;
+        }
+        self.jump_button
+            .set_visible(self.unread_count > 0 || self.is_far_from_bottom());
+        if self.unread_count > 0 {
+            self.jump_button
+                .set_label(&format!("{} new message(s)", self.unread_count));
+        } else {
+            self.jump_button.set_label("Jump to latest");
+        }
+    }
+
+    fn scroll_to_bottom(&self) {
+        let adjustment = self.scrolled_window.vadjustment();
+        adjustment.set_value(adjustment.upper() - adjustment.page_size());
+    }
+
+    fn update_selection_bar(&self) {
+        let count = self.forward_selection.len();
+        self.selection_label
+            .set_label(&format!("{} message(s) selected", count));
+        self.selection_bar.set_reveal_child(count > 0);
+    }
+}
+
+/// Leave some room for the rounding of the adjustment.
+fn is_at_bottom(adjustment: &Adjustment) -> bool {
+    adjustment.upper() - adjustment.value() - adjustment.page_size() < 32.0
+}
+
+fn bound_row(list_item: &ListItem) -> Option<(BoxedAnyObject, Box)> {
+    let row = list_item.item()?.downcast::<BoxedAnyObject>().ok()?;
+    let root = list_item.child()?.downcast::<Box>().ok()?;
+    Some((row, root))
+}
+
+/// Only the visible rows of the list view get realized, and their widgets are
+/// rebuilt whenever a list item is bound to another row.
+fn row_factory(input: &Sender<ChatroomMsg>) -> SignalListItemFactory {
+    let factory = SignalListItemFactory::new();
+    factory.connect_setup(|_, list_item| {
+        let root = Box::builder()
+            .orientation(Orientation::Horizontal)
+            .spacing(8)
+            .margin_bottom(8)
+            .build();
+        list_item.set_activatable(false);
+        list_item.set_selectable(false);
+        list_item.set_child(Some(&root));
+    });
+
+    let input = input.clone();
+    factory.connect_bind(move |_, list_item| {
+        if let Some((row, root)) = bound_row(list_item) {
+            row.borrow_mut::<Row>().bind(&root, &input);
+        }
+    });
+    factory.connect_unbind(|_, list_item| {
+        if let Some((row, root)) = bound_row(list_item) {
+            row.borrow_mut::<Row>().unbind(&root);
+        }
+    });
+
+    factory
+}
+
+/// Refresh the members of the group, whose names the history is shown
+/// with. The history waits for them if none are cached yet.
+async fn refresh_members(group_id: i64, input: Sender<ChatroomMsg>) {
+    let cached = has_group_members(group_id);
+    if cached {
+        input.send(ChatroomMsg::LoadHistory);
+    }
+    if let Err(err) = refresh_group_members(group_id).await {
+        log::warn!("Failed to refresh the members of {}: {}", group_id, err);
+    }
+    if !cached {
+        input.send(ChatroomMsg::LoadHistory);
     }
 }
 
-async fn send_message(target: i64, is_group: bool, content: String, output: Sender<MainMsg>) {
+/// Send the pending message `id`, which is queued again if it fails.
+async fn send_message(
+    target: i64,
+    kind: ChatKind,
+    id: i64,
+    content: String,
+    input: Sender<ChatroomMsg>,
+    output: Sender<MainMsg>,
+) {
     let client = CLIENT.get().unwrap();
-    let message = MessageChain::new(elem::Text::new(content.clone()));
-    let self_account = *ACCOUNT.get().unwrap();
-    if is_group {
-        match client.send_group_message(target, message).await {
-            Ok(_) => output.send(MainMsg::GroupMessage {
-                group_id: target,
-                message: Message {
-                    sender_id: self_account,
-                    sender_name: get_friend_remark(self_account),
-                    contents: vec![Content::Text(content)],
-                },
-            }),
-            Err(err) => panic!("err: {:?}", err),
-        }
-    } else {
-        match client.send_friend_message(target, message).await {
-            Ok(_) => output.send(MainMsg::FriendMessage {
-                friend_id: target,
-                message: Message {
-                    sender_id: self_account,
-                    sender_name: get_friend_remark(self_account),
-                    contents: vec![Content::Text(content)],
-                },
-            }),
-            Err(err) => panic!("err: {:?}", err),
+    let chain = MessageChain::new(elem::Text::new(content.clone()));
+    let result = match kind {
+        ChatKind::Temp { group_id } => {
+            client
+                .send_group_temp_message(group_id, target, chain.clone())
+                .await
+        }
+        ChatKind::Group => client.send_group_message(target, chain.clone()).await,
+        ChatKind::Friend => client.send_friend_message(target, chain.clone()).await,
+    };
+    let receipt = match result {
+        Ok(receipt) => receipt,
+        Err(err) => {
+            log::warn!("Failed to send the message: {}", err);
+            input.send(ChatroomMsg::SendFailed(id));
+            return;
         }
     };
+
+    save_sent_message(target, kind, &receipt, &chain, None);
+    input.send(ChatroomMsg::Sent(id));
+    let self_account = *ACCOUNT.get().unwrap();
+    let message = Message {
+        sender_id: self_account,
+        sender_name: get_friend_remark(self_account),
+        contents: vec![Content::Text(content)],
+        elements: chain,
+    };
+    output.send(match kind {
+        ChatKind::Temp { group_id } => MainMsg::TempMessage {
+            group_id,
+            user_id: target,
+            message,
+        },
+        ChatKind::Group => MainMsg::GroupMessage {
+            group_id: target,
+            message,
+        },
+        ChatKind::Friend => MainMsg::FriendMessage {
+            friend_id: target,
+            message,
+        },
+    });
 }
 
 #[derive(Debug)]
 pub(crate) enum ChatroomMsg {
-    AddMessage(Message),
     SendMessage(String),
+    /// Send the messages queued while offline.
+    SendQueued,
+    /// The pending message of the id was sent.
+    Sent(i64),
+    /// The pending message of the id failed to be sent, so it is queued.
+    SendFailed(i64),
+    LoadHistory,
+    HistoryLoaded(HistoryPage),
+    /// The server history could not be fetched, which may be retried.
+    HistoryFailed(String),
+    Scrolled,
+    JumpToLatest,
+    ForwardMessages(Vec<Message>),
+    AddToForwardSelection(Message),
+    ClearForwardSelection,
+    ForwardSelection,
+    ShowProfile(i64),
 }
 
 pub(crate) struct ChatroomInitParams {
     pub account: i64,
-    pub is_group: bool,
-    pub messages: VecDeque<Message>,
+    pub kind: ChatKind,
 }
 
 impl FactoryComponent<Stack, MainMsg> for Chatroom {
@@ -109,14 +425,15 @@ impl FactoryComponent<Stack, MainMsg> for Chatroom {
         let root = Box::new(Orientation::Vertical, 0);
 
         relm4::view! {
-            view = &ScrolledWindow {
-                set_vexpand: true,
-                set_hexpand: true,
-                set_child: Some(self.messages.widget())
+            view = &Overlay {
+                set_child: Some(&self.scrolled_window),
+                add_overlay: &self.jump_button,
             }
         }
 
+        root.append(&self.offline_banner);
         root.append(&view);
+        root.append(&self.selection_bar);
         root.append(&self.input_box);
         root
     }
@@ -129,11 +446,7 @@ impl FactoryComponent<Stack, MainMsg> for Chatroom {
         _input: &Sender<Self::Input>,
         _output: &Sender<Self::Output>,
     ) -> Self::Widgets {
-        let title = &format!(
-            "{} {}",
-            self.account,
-            if self.is_group { "group" } else { "friend" }
-        );
+        let title = &chatroom_name(self.account, self.kind);
         returned_widget.set_name(title);
         returned_widget.set_title(title);
     }
@@ -144,18 +457,62 @@ impl FactoryComponent<Stack, MainMsg> for Chatroom {
         input: &Sender<Self::Input>,
         _output: &Sender<Self::Output>,
     ) -> Self {
-        let ChatroomInitParams {
-            account,
-            is_group,
-            messages: messages_src,
-        } = init_params;
-        let messages_box = Box::new(Orientation::Vertical, 2);
-        messages_box.set_css_classes(&["chatroom-box"]);
+        let ChatroomInitParams { account, kind } = init_params;
+        let messages = gio::ListStore::new(BoxedAnyObject::static_type());
+        let list_view = ListView::new(
+            Some(&NoSelection::new(Some(&messages))),
+            Some(&row_factory(input)),
+        );
+        list_view.set_css_classes(&["chatroom-box"]);
+
+        relm4::view! {
+            scrolled_window = &ScrolledWindow {
+                set_vexpand: true,
+                set_hexpand: true,
+                set_child: Some(&list_view),
+            }
+        }
+
+        let stick_to_bottom = Rc::new(Cell::new(true));
+        let adjustment = scrolled_window.vadjustment();
+        adjustment.connect_value_changed(
+            glib::clone!(@strong input, @strong stick_to_bottom => move |adjustment| {
+                stick_to_bottom.set(is_at_bottom(adjustment));
+                if adjustment.value() < adjustment.page_size() / 2.0 {
+                    input.send(ChatroomMsg::LoadHistory);
+                }
+                input.send(ChatroomMsg::Scrolled);
+            }),
+        );
+        // The upper bound grows as new rows are realized or images are loaded.
+        adjustment.connect_changed(glib::clone!(@strong stick_to_bottom => move |adjustment| {
+            if stick_to_bottom.get() {
+                adjustment.set_value(adjustment.upper() - adjustment.page_size());
+            }
+        }));
 
-        let messages: FactoryVecDeque<Box, MessageGroup, ChatroomMsg> =
-            FactoryVecDeque::new(messages_box, input);
-        for msg_src in messages_src.iter() {
-            input.send(ChatroomMsg::AddMessage(msg_src.clone()))
+        relm4::view! {
+            jump_button = &Button {
+                set_label: "Jump to latest",
+                set_visible: false,
+                set_halign: Align::End,
+                set_valign: Align::End,
+                set_margin_all: 12,
+                set_css_classes: &["osd", "pill"],
+                connect_clicked[input] => move |_| {
+                    input.send(ChatroomMsg::JumpToLatest);
+                }
+            }
+        }
+
+        let history_end = get_last_message_rowid(account, kind).unwrap_or_else(|err| {
+            log::warn!("Failed to load history: {}", err);
+            0
+        });
+        if kind.is_group() {
+            task::spawn(refresh_members(account, input.clone()));
+        } else {
+            input.send(ChatroomMsg::LoadHistory);
         }
 
         relm4::view! {
@@ -193,30 +550,189 @@ impl FactoryComponent<Stack, MainMsg> for Chatroom {
             }
         }
 
-        Chatroom {
+        relm4::view! {
+            selection_bar = &Revealer {
+                set_child = Some(&Box) {
+                    set_margin_start: 8,
+                    set_margin_end: 8,
+                    set_spacing: 8,
+                    #[name = "selection_label"]
+                    Label {
+                        set_hexpand: true,
+                        set_xalign: 0.0,
+                    },
+                    Button {
+                        set_label: "Clear",
+                        connect_clicked[input] => move |_| {
+                            input.send(ChatroomMsg::ClearForwardSelection);
+                        }
+                    },
+                    Button {
+                        set_label: "Forward…",
+                        add_css_class: "suggested-action",
+                        connect_clicked[input] => move |_| {
+                            input.send(ChatroomMsg::ForwardSelection);
+                        }
+                    },
+                }
+            }
+        }
+
+        relm4::view! {
+            offline_banner = &Revealer {
+                set_reveal_child: connection_state().0 != ConnectionState::Online,
+                set_child = Some(&Label) {
+                    set_label: "You are offline. Messages will be queued and sent once reconnected.",
+                    set_wrap: true,
+                    set_margin_all: 8,
+                    add_css_class: "warning",
+                }
+            }
+        }
+
+        let mut chatroom = Chatroom {
             account,
-            is_group,
+            kind,
             messages,
+            list_view,
+            scrolled_window,
+            jump_button,
+            input: input.clone(),
             input_box,
+            oldest: None,
+            history_end,
+            history_state: HistoryState::Idle,
+            unread_count: 0,
+            stick_to_bottom,
+            forward_selection: Vec::new(),
+            selection_bar,
+            selection_label,
+            online: !offline_banner.reveals_child(),
+            offline_banner,
+            pending: Vec::new(),
+        };
+        if kind == ChatKind::Group {
+            // The notice of the event is only shown live, so it is shown
+            // again from the stored state.
+            if let Some(left) = get_group_left(account) {
+                chatroom.push_notice(describe_group_left(left).to_string());
+                chatroom.set_read_only();
+            }
         }
+
+        // The messages left unsent by the last session.
+        match get_pending_messages(account, kind) {
+            Ok(pending) => {
+                for (id, content) in pending {
+                    chatroom.push_pending(PendingMessage {
+                        id,
+                        content,
+                        queued: true,
+                    });
+                }
+            }
+            Err(err) => log::warn!("Failed to load the pending messages: {}", err),
+        }
+        if chatroom.online {
+            input.send(ChatroomMsg::SendQueued);
+        }
+
+        chatroom
     }
 
     fn update(
         &mut self,
         relm_msg: Self::Input,
-        _input: &Sender<Self::Input>,
+        input: &Sender<Self::Input>,
         output: &Sender<Self::Output>,
     ) -> Option<Self::Command> {
         match relm_msg {
-            ChatroomMsg::AddMessage(message) => self.push_message(message),
             ChatroomMsg::SendMessage(content) => {
-                task::spawn(send_message(
-                    self.account,
-                    self.is_group,
-                    content,
-                    output.clone(),
-                ));
+                match add_pending_message(self.account, self.kind, &content) {
+                    Ok(id) => {
+                        self.stick_to_bottom.set(true);
+                        self.scroll_to_bottom();
+                        self.push_pending(PendingMessage {
+                            id,
+                            content,
+                            queued: true,
+                        });
+                        if self.online {
+                            self.send_pending(self.pending.len() - 1, output);
+                        }
+                    }
+                    Err(err) => log::warn!("Failed to queue the message: {}", err),
+                }
+            }
+            ChatroomMsg::SendQueued => {
+                for index in 0..self.pending.len() {
+                    if self.pending[index].queued {
+                        self.send_pending(index, output);
+                    }
+                }
+            }
+            ChatroomMsg::Sent(id) => self.remove_pending(id),
+            ChatroomMsg::SendFailed(id) => {
+                if let Some(index) = self.pending.iter().position(|pending| pending.id == id) {
+                    self.pending[index].queued = true;
+                    self.update_pending(index);
+                }
+            }
+            ChatroomMsg::LoadHistory => {
+                if self.history_state == HistoryState::Idle {
+                    self.history_state = HistoryState::Loading;
+                    task::spawn(load_history(
+                        self.account,
+                        self.kind,
+                        self.oldest,
+                        self.history_end,
+                        input.clone(),
+                    ));
+                }
+            }
+            ChatroomMsg::HistoryLoaded(HistoryPage { messages, oldest }) => {
+                let is_first_page = self.oldest.is_none();
+                match oldest {
+                    Some(oldest) => {
+                        self.oldest = Some(oldest);
+                        self.history_state = HistoryState::Idle;
+                    }
+                    None => self.history_state = HistoryState::Exhausted,
+                }
+                self.prepend_history(messages);
+                if is_first_page {
+                    let input = input.clone();
+                    glib::idle_add_local_once(move || input.send(ChatroomMsg::JumpToLatest));
+                }
+            }
+            ChatroomMsg::HistoryFailed(err) => {
+                log::warn!("{}", err);
+                self.history_state = HistoryState::Idle;
+            }
+            ChatroomMsg::Scrolled => self.update_jump_button(),
+            ChatroomMsg::JumpToLatest => {
+                self.stick_to_bottom.set(true);
+                self.scroll_to_bottom();
+                self.unread_count = 0;
+                self.update_jump_button();
+            }
+            ChatroomMsg::ForwardMessages(messages) => {
+                output.send(MainMsg::ForwardMessages(messages));
+            }
+            ChatroomMsg::AddToForwardSelection(message) => {
+                self.forward_selection.push(message);
+                self.update_selection_bar();
+            }
+            ChatroomMsg::ClearForwardSelection => {
+                self.forward_selection.clear();
+                self.update_selection_bar();
+            }
+            ChatroomMsg::ForwardSelection => {
+                let messages = std::mem::take(&mut self.forward_selection);
+                self.update_selection_bar();
+                output.send(MainMsg::ForwardMessages(messages));
             }
+            ChatroomMsg::ShowProfile(user_id) => output.send(MainMsg::ShowProfile(user_id)),
         }
         None
     }
@@ -0,0 +1,87 @@
+use relm4::{gtk, Sender, WidgetPlus};
+
+use gtk::prelude::*;
+use gtk::{Align, Box, Justification, Label, Orientation};
+
+use super::message_group::MessageGroup;
+use super::ChatroomMsg;
+
+/// A message of our own which is not sent yet.
+#[derive(Debug, Clone)]
+pub(crate) struct PendingMessage {
+    /// The id in the pending messages table.
+    pub id: i64,
+    pub content: String,
+    /// Whether it waits for the connection, rather than being sent.
+    pub queued: bool,
+}
+
+/// A row of the chatroom's `ListView`.
+#[derive(Debug)]
+pub(crate) enum Row {
+    Messages(MessageGroup),
+    /// A centred system notice, like "Alice joined".
+    Notice(String),
+    /// Kept after the other rows until it is sent.
+    Pending(PendingMessage),
+}
+
+impl Row {
+    pub(crate) fn bind(&mut self, root: &Box, output: &Sender<ChatroomMsg>) {
+        match self {
+            Row::Messages(group) => group.bind(root, output),
+            Row::Notice(text) => {
+                relm4::view! {
+                    label = Label {
+                        set_label: text,
+                        set_wrap: true,
+                        set_justify: Justification::Center,
+                        set_css_classes: &["caption", "dim-label"],
+                    }
+                }
+                root.set_halign(Align::Center);
+                root.append(&label);
+            }
+            Row::Pending(pending) => {
+                let state = if pending.queued {
+                    "Not sent, waiting for the connection"
+                } else {
+                    "Sending…"
+                };
+                relm4::view! {
+                    bubble = Box {
+                        set_orientation: Orientation::Vertical,
+                        set_css_classes: &["card", "message-box"],
+                        set_margin_all: 2,
+                        Label {
+                            set_label: &pending.content,
+                            set_wrap: true,
+                            set_selectable: true,
+                            set_xalign: 0.0,
+                            set_margin_all: 8,
+                        },
+                        Label {
+                            set_label: state,
+                            set_xalign: 1.0,
+                            set_margin_start: 8,
+                            set_margin_end: 8,
+                            set_margin_bottom: 4,
+                            set_css_classes: &["caption", "dim-label"],
+                        },
+                    }
+                }
+                root.set_halign(Align::End);
+                root.append(&bubble);
+            }
+        }
+    }
+
+    pub(crate) fn unbind(&mut self, root: &Box) {
+        while let Some(child) = root.first_child() {
+            root.remove(&child);
+        }
+        if let Row::Messages(group) = self {
+            group.unbind();
+        }
+    }
+}
@@ -0,0 +1,296 @@
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::Duration;
+
+use relm4::gtk;
+
+use gtk::gio::{self, AppLaunchContext};
+use gtk::glib::{self, clone, MainContext, WeakRef, PRIORITY_DEFAULT};
+use gtk::pango::WrapMode;
+use gtk::prelude::*;
+use gtk::{Align, Box, Button, Label, Orientation, Picture, ProgressBar};
+use resource_loader::MediaVideo;
+use tokio::task;
+
+use crate::utils::media::{download_video, get_video_path, load_picture, probe_duration};
+use crate::utils::message::Video;
+use crate::utils::DirAction;
+
+enum DownloadEvent {
+    Progress(f64),
+    Finished(PathBuf),
+    Failed(String),
+}
+
+fn format_size(size: i64) -> String {
+    let size = size as f64;
+    if size >= 1024.0 * 1024.0 {
+        format!("{:.1} MB", size / 1024.0 / 1024.0)
+    } else {
+        format!("{:.1} KB", size / 1024.0)
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+fn open_video(path: &Path) {
+    match glib::filename_to_uri(path, None) {
+        Ok(uri) => {
+            if let Err(err) = gio::AppInfo::launch_default_for_uri(&uri, None::<&AppLaunchContext>)
+            {
+                log::warn!("Failed to open video: {}", err);
+            }
+        }
+        Err(err) => log::warn!("Failed to open video: {}", err),
+    }
+}
+
+#[derive(Debug)]
+enum Download {
+    Idle,
+    Running(f64),
+    Finished(PathBuf),
+    Failed,
+    /// The url is unknown, or the cache is unusable.
+    Unavailable,
+}
+
+struct VideoInner {
+    video: Video,
+    path: Option<PathBuf>,
+    download: RefCell<Download>,
+    /// Probed once the video is downloaded.
+    duration: Cell<Option<Duration>>,
+    /// The widgets of the bubble while it is shown.
+    view: RefCell<Option<(WeakRef<Button>, WeakRef<ProgressBar>, WeakRef<Label>)>>,
+}
+
+/// The download of a video message. It is kept by the message group, so a
+/// download is started at most once, however often the row of the bubble is
+/// recycled and rebuilt.
+#[derive(Clone)]
+pub(super) struct VideoState(Rc<VideoInner>);
+
+impl fmt::Debug for VideoState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VideoState")
+            .field("md5", &self.0.video.md5)
+            .field("download", &self.0.download.borrow())
+            .finish()
+    }
+}
+
+impl VideoState {
+    pub(super) fn new(video: &Video) -> Self {
+        let path = match get_video_path(video, DirAction::CreateAll) {
+            Ok(path) => Some(path),
+            Err(err) => {
+                log::warn!("{}", err);
+                None
+            }
+        };
+        let download = match &path {
+            Some(path) if path.exists() => Download::Finished(path.clone()),
+            Some(_) if video.url.is_some() => Download::Idle,
+            _ => Download::Unavailable,
+        };
+        let state = VideoState(Rc::new(VideoInner {
+            video: video.clone(),
+            path,
+            download: RefCell::new(download),
+            duration: Cell::new(None),
+            view: RefCell::new(None),
+        }));
+
+        let finished = match &*state.0.download.borrow() {
+            Download::Finished(path) => Some(path.clone()),
+            _ => None,
+        };
+        match finished {
+            Some(path) => state.probe_duration(path),
+            None if (video.size as u64) <= MediaVideo::auto_download_limit() => {
+                state.start_download()
+            }
+            None => (),
+        }
+        state
+    }
+
+    fn render(&self) {
+        let inner = &self.0;
+        let view = inner.view.borrow();
+        let (button, progress_bar, info_label) = match view.as_ref() {
+            Some((button, progress_bar, info_label)) => {
+                match (
+                    button.upgrade(),
+                    progress_bar.upgrade(),
+                    info_label.upgrade(),
+                ) {
+                    (Some(button), Some(progress_bar), Some(info_label)) => {
+                        (button, progress_bar, info_label)
+                    }
+                    _ => return,
+                }
+            }
+            None => return,
+        };
+
+        let (label, sensitive) = match &*inner.download.borrow() {
+            Download::Idle => ("Download", true),
+            Download::Running(_) => ("Download", false),
+            Download::Finished(_) => ("Open", true),
+            Download::Failed => ("Retry", true),
+            Download::Unavailable => ("Unavailable", false),
+        };
+        button.set_label(label);
+        button.set_sensitive(sensitive);
+
+        match &*inner.download.borrow() {
+            Download::Running(fraction) => {
+                progress_bar.set_fraction(*fraction);
+                progress_bar.set_visible(true);
+            }
+            _ => progress_bar.set_visible(false),
+        }
+
+        let size = format_size(inner.video.size);
+        match inner.duration.get() {
+            Some(duration) => {
+                info_label.set_label(&format!("{} · {}", size, format_duration(duration)))
+            }
+            None => info_label.set_label(&size),
+        }
+    }
+
+    fn set_download(&self, download: Download) {
+        self.0.download.replace(download);
+        self.render();
+    }
+
+    /// Probe the duration of the downloaded video in background.
+    fn probe_duration(&self, path: PathBuf) {
+        let (tx, rx) = MainContext::channel(PRIORITY_DEFAULT);
+        task::spawn_blocking(move || {
+            if let Some(duration) = probe_duration(&path) {
+                tx.send(duration).ok();
+            }
+        });
+
+        let state = self.clone();
+        rx.attach(None, move |duration| {
+            state.0.duration.set(Some(duration));
+            state.render();
+            glib::Continue(false)
+        });
+    }
+
+    fn start_download(&self) {
+        let inner = &self.0;
+        let (url, path) = match (&inner.video.url, &inner.path) {
+            (Some(url), Some(path)) => (url.clone(), path.clone()),
+            _ => return,
+        };
+        if !matches!(*inner.download.borrow(), Download::Idle | Download::Failed) {
+            return;
+        }
+        self.set_download(Download::Running(0.0));
+
+        let (tx, rx) = MainContext::channel(PRIORITY_DEFAULT);
+        task::spawn(async move {
+            let progress_tx = tx.clone();
+            let event = match download_video(url, path, move |fraction| {
+                progress_tx.send(DownloadEvent::Progress(fraction)).ok();
+            })
+            .await
+            {
+                Ok(path) => DownloadEvent::Finished(path),
+                Err(err) => DownloadEvent::Failed(err.to_string()),
+            };
+            tx.send(event).ok();
+        });
+
+        let state = self.clone();
+        rx.attach(None, move |event| match event {
+            DownloadEvent::Progress(fraction) => {
+                state.set_download(Download::Running(fraction));
+                glib::Continue(true)
+            }
+            DownloadEvent::Finished(path) => {
+                state.probe_duration(path.clone());
+                state.set_download(Download::Finished(path));
+                glib::Continue(false)
+            }
+            DownloadEvent::Failed(err) => {
+                log::warn!("Failed to download video: {}", err);
+                state.set_download(Download::Failed);
+                glib::Continue(false)
+            }
+        });
+    }
+
+    fn activate(&self) {
+        let finished = match &*self.0.download.borrow() {
+            Download::Finished(path) => Some(path.clone()),
+            _ => None,
+        };
+        match finished {
+            Some(path) => open_video(&path),
+            None => self.start_download(),
+        }
+    }
+}
+
+/// A video message bubble, showing the download of `state`.
+pub(super) fn video_widget(state: &VideoState) -> Box {
+    let video = &state.0.video;
+    relm4::view! {
+        root = Box {
+            set_orientation: Orientation::Vertical,
+            set_spacing: 4,
+            set_width_request: 240,
+            #[name = "thumbnail"]
+            Picture {
+                set_height_request: 135,
+                set_can_shrink: true,
+            },
+            Label {
+                set_label: &video.name,
+                add_css_class: "heading",
+                set_xalign: 0.0,
+                set_wrap: true,
+                set_wrap_mode: WrapMode::WordChar,
+            },
+            #[name = "info_label"]
+            Label {
+                add_css_class: "caption",
+                set_xalign: 0.0,
+            },
+            #[name = "progress_bar"]
+            ProgressBar {
+                set_visible: false,
+            },
+            #[name = "button"]
+            Button {
+                set_halign: Align::Start,
+            }
+        }
+    }
+
+    load_picture(&thumbnail, video.thumb_url.clone());
+
+    state.0.view.replace(Some((
+        button.downgrade(),
+        progress_bar.downgrade(),
+        info_label.downgrade(),
+    )));
+    state.render();
+
+    button.connect_clicked(clone!(@strong state => move |_| state.activate()));
+
+    root
+}
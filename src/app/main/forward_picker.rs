@@ -0,0 +1,414 @@
+use std::boxed;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use relm4::{adw, gtk, ComponentParts, ComponentSender, SimpleComponent, WidgetPlus};
+
+use adw::{prelude::*, HeaderBar, Window};
+use gtk::{Align, Box, Button, CheckButton, Label, ListBox, Orientation, ScrolledWindow, Switch};
+use ricq::msg::elem::RQElem;
+use ricq::msg::{elem, MessageChain};
+use ricq::pb::msg::{Elem, Ptt};
+use ricq::structs::{ForwardMessage, ForwardNode, MessageNode, MessageReceipt};
+use ricq::Client;
+use tokio::task;
+
+use crate::db::sql::{get_friend_remark, get_friends, get_group_name, get_groups};
+use crate::handler::{save_sent_message, ACCOUNT, CLIENT};
+use crate::utils::message::{Content, Forward, Message};
+
+use super::{ChatKind, MainMsg};
+
+pub(crate) struct ForwardPickerModel {
+    window: Window,
+    messages: Vec<Message>,
+    targets: HashSet<(i64, bool)>,
+    /// A chat may be listed in several sections, whose check buttons are
+    /// kept in sync.
+    check_buttons: HashMap<(i64, bool), Vec<CheckButton>>,
+    merged: bool,
+}
+
+pub(crate) struct Payload {
+    pub window: Window,
+    pub messages: Vec<Message>,
+    /// (account, is_group) of the chats in the sidebar
+    pub recent_chats: Vec<(i64, bool)>,
+}
+
+#[derive(Debug)]
+pub(crate) enum ForwardPickerMsg {
+    Toggle(i64, bool, bool),
+    SetMerged(bool),
+    Send,
+}
+
+/// Rebuild the elements of `message` for `target`. The images have to be
+/// uploaded again, as they are bound to the chat they were sent in, while
+/// the other elements, like videos, cards and forwards, are sent as they are.
+async fn build_message_chain(
+    client: &Client,
+    target: i64,
+    is_group: bool,
+    message: &Message,
+) -> Result<MessageChain, boxed::Box<dyn Error + Send + Sync>> {
+    let original = Vec::<Elem>::from(message.elements.clone());
+    if original.is_empty() {
+        // Voice messages have no elements, and can not be nested in a
+        // merged forward.
+        return Ok(MessageChain::new(elem::Text::new(message.text())));
+    }
+
+    let mut elems = Vec::new();
+    for elem in original {
+        let url = match MessageChain::from(vec![elem.clone()]).into_iter().next() {
+            Some(RQElem::FriendImage(image)) => image.url(),
+            Some(RQElem::GroupImage(image)) => image.url(),
+            _ => {
+                elems.push(elem);
+                continue;
+            }
+        };
+        let data = reqwest::get(url).await?.bytes().await?.to_vec();
+        let image = if is_group {
+            MessageChain::new(client.upload_group_image(target, data).await?)
+        } else {
+            MessageChain::new(client.upload_friend_image(target, data).await?)
+        };
+        elems.extend(Vec::<Elem>::from(image));
+    }
+
+    Ok(MessageChain::from(elems))
+}
+
+/// Upload the voice at `url` to `target` and send it.
+async fn send_audio(
+    client: &Client,
+    target: i64,
+    is_group: bool,
+    url: &str,
+    duration: i32,
+) -> Result<(MessageReceipt, Ptt), boxed::Box<dyn Error + Send + Sync>> {
+    let data = reqwest::get(url).await?.bytes().await?.to_vec();
+    if is_group {
+        // 1 is the codec of SILK.
+        let audio = client.upload_group_audio(target, data, 1).await?;
+        let ptt = audio.0.clone();
+        Ok((client.send_group_audio(target, audio).await?, ptt))
+    } else {
+        let duration = Duration::from_secs(duration.max(0) as u64);
+        let audio = client.upload_friend_audio(target, data, duration).await?;
+        let ptt = audio.0.clone();
+        Ok((client.send_friend_audio(target, audio).await?, ptt))
+    }
+}
+
+fn echo_message(output: &relm4::Sender<MainMsg>, target: i64, is_group: bool, message: Message) {
+    if is_group {
+        output.send(MainMsg::GroupMessage {
+            group_id: target,
+            message,
+        });
+    } else {
+        output.send(MainMsg::FriendMessage {
+            friend_id: target,
+            message,
+        });
+    }
+}
+
+async fn send_separately(
+    client: &Client,
+    target: i64,
+    is_group: bool,
+    messages: &[Message],
+    output: &relm4::Sender<MainMsg>,
+) -> Result<(), boxed::Box<dyn Error + Send + Sync>> {
+    let self_account = *ACCOUNT.get().unwrap();
+    let kind = if is_group {
+        ChatKind::Group
+    } else {
+        ChatKind::Friend
+    };
+    for message in messages {
+        let elements = if let [Content::Audio { url, duration }] = message.contents.as_slice() {
+            let (receipt, ptt) = send_audio(client, target, is_group, url, *duration).await?;
+            save_sent_message(target, kind, &receipt, &message.elements, Some(&ptt));
+            message.elements.clone()
+        } else {
+            let chain = build_message_chain(client, target, is_group, message).await?;
+            let receipt = if is_group {
+                client.send_group_message(target, chain.clone()).await?
+            } else {
+                client.send_friend_message(target, chain.clone()).await?
+            };
+            save_sent_message(target, kind, &receipt, &chain, None);
+            chain
+        };
+        echo_message(
+            output,
+            target,
+            is_group,
+            Message {
+                sender_id: self_account,
+                sender_name: get_friend_remark(self_account),
+                contents: message.contents.clone(),
+                elements,
+            },
+        );
+    }
+
+    Ok(())
+}
+
+async fn send_merged(
+    client: &Client,
+    group_id: i64,
+    messages: &[Message],
+    output: &relm4::Sender<MainMsg>,
+) -> Result<(), boxed::Box<dyn Error + Send + Sync>> {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i32)
+        .unwrap_or_default();
+
+    let mut nodes = Vec::new();
+    for message in messages {
+        nodes.push(ForwardMessage::Message(MessageNode {
+            sender_id: message.sender_id,
+            time,
+            sender_name: message.sender_name.clone(),
+            elements: build_message_chain(client, group_id, true, message).await?,
+        }));
+    }
+    client
+        .send_group_forward_message(group_id, nodes.clone())
+        .await?;
+
+    let self_account = *ACCOUNT.get().unwrap();
+    let forward = Forward::from(ForwardNode {
+        sender_id: self_account,
+        time,
+        sender_name: get_friend_remark(self_account),
+        nodes,
+    });
+    echo_message(
+        output,
+        group_id,
+        true,
+        Message {
+            sender_id: self_account,
+            sender_name: get_friend_remark(self_account),
+            contents: vec![Content::Forward(forward)],
+            elements: MessageChain::default(),
+        },
+    );
+
+    Ok(())
+}
+
+/// Merged forwards can only be sent to groups, so friends always get the
+/// messages one by one.
+async fn forward_messages(
+    targets: Vec<(i64, bool)>,
+    messages: Vec<Message>,
+    merged: bool,
+    output: relm4::Sender<MainMsg>,
+) {
+    let client = CLIENT.get().unwrap();
+    for (target, is_group) in targets {
+        let res = if merged && is_group {
+            send_merged(client, target, &messages, &output).await
+        } else {
+            send_separately(client, target, is_group, &messages, &output).await
+        };
+        if let Err(err) = res {
+            output.send(MainMsg::PushToast(format!("Failed to forward: {}", err)));
+        }
+    }
+}
+
+/// Append a section listing `items`, and record its check buttons in
+/// `check_buttons`.
+fn append_section(
+    container: &Box,
+    title: &str,
+    items: Vec<(i64, bool, String)>,
+    check_buttons: &mut HashMap<(i64, bool), Vec<CheckButton>>,
+    sender: &ComponentSender<ForwardPickerModel>,
+) {
+    if items.is_empty() {
+        return;
+    }
+
+    relm4::view! {
+        section = Box {
+            set_orientation: Orientation::Vertical,
+            set_spacing: 4,
+            Label {
+                set_label: title,
+                add_css_class: "heading",
+                set_halign: Align::Start,
+            },
+            #[name = "list"]
+            ListBox {
+                add_css_class: "boxed-list",
+            }
+        }
+    }
+
+    for (account, is_group, name) in items {
+        relm4::view! {
+            check_button = CheckButton {
+                set_label: Some(&name),
+                set_margin_all: 8,
+                connect_toggled[sender] => move |button| {
+                    sender.input(ForwardPickerMsg::Toggle(account, is_group, button.is_active()));
+                },
+            }
+        }
+        list.append(&check_button);
+        check_buttons
+            .entry((account, is_group))
+            .or_default()
+            .push(check_button);
+    }
+
+    container.append(&section);
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for ForwardPickerModel {
+    type Input = ForwardPickerMsg;
+    type Output = MainMsg;
+    type InitParams = Payload;
+    type Widgets = ForwardPickerWidgets;
+
+    view! {
+        #[root]
+        root = Box {
+            set_orientation: Orientation::Vertical,
+            HeaderBar {
+                set_title_widget = Some(&Label) {
+                    set_label: &format!("Forward {} message(s)", model.messages.len()),
+                },
+                pack_end = &Button {
+                    set_label: "Send",
+                    add_css_class: "suggested-action",
+                    #[watch]
+                    set_sensitive: !model.targets.is_empty(),
+                    connect_clicked[sender] => move |_| {
+                        sender.input(ForwardPickerMsg::Send);
+                    },
+                },
+            },
+            Box {
+                set_margin_all: 12,
+                set_spacing: 8,
+                Label {
+                    set_label: "Send as merged forward (groups only)",
+                    set_hexpand: true,
+                    set_halign: Align::Start,
+                },
+                Switch {
+                    set_active: model.merged,
+                    connect_state_set[sender] => move |_, state| {
+                        sender.input(ForwardPickerMsg::SetMerged(state));
+                        gtk::Inhibit(false)
+                    },
+                },
+            },
+            ScrolledWindow {
+                set_vexpand: true,
+                set_child: sections = Some(&Box) {
+                    set_orientation: Orientation::Vertical,
+                    set_spacing: 16,
+                    set_margin_all: 12,
+                }
+            }
+        }
+    }
+
+    fn init(
+        params: Self::InitParams,
+        root: &Self::Root,
+        sender: &ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let mut model = ForwardPickerModel {
+            window: params.window,
+            merged: params.messages.len() > 1,
+            messages: params.messages,
+            targets: HashSet::new(),
+            check_buttons: HashMap::new(),
+        };
+        let widgets = view_output!();
+
+        let recent_chats = params
+            .recent_chats
+            .into_iter()
+            .map(|(account, is_group)| {
+                let name = if is_group {
+                    get_group_name(account)
+                } else {
+                    get_friend_remark(account)
+                };
+                (account, is_group, name)
+            })
+            .collect();
+        let friends = get_friends()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|friend| (friend.id, false, friend.remark))
+            .collect();
+        let groups = get_groups()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|group| (group.id, true, group.name))
+            .collect();
+
+        let sections = [
+            ("Recent Chats", recent_chats),
+            ("Friends", friends),
+            ("Groups", groups),
+        ];
+        for (title, items) in sections {
+            append_section(
+                &widgets.sections,
+                title,
+                items,
+                &mut model.check_buttons,
+                sender,
+            );
+        }
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: ForwardPickerMsg, sender: &ComponentSender<Self>) {
+        use ForwardPickerMsg::*;
+        match msg {
+            Toggle(account, is_group, active) => {
+                if active {
+                    self.targets.insert((account, is_group));
+                } else {
+                    self.targets.remove(&(account, is_group));
+                }
+                // Setting the same state again does not emit `toggled`.
+                for button in self.check_buttons.get(&(account, is_group)).unwrap() {
+                    button.set_active(active);
+                }
+            }
+            SetMerged(merged) => self.merged = merged,
+            Send => {
+                task::spawn(forward_messages(
+                    self.targets.drain().collect(),
+                    self.messages.clone(),
+                    self.merged,
+                    sender.output_sender().clone(),
+                ));
+                self.window.close();
+            }
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use std::error::Error;
+
+use reqwest::header::COOKIE;
+use serde_json::Value;
+
+use crate::handler::{ACCOUNT, CLIENT};
+
+/// An announcement of a group, also known as a group notice.
+#[derive(Debug, Clone)]
+pub(crate) struct Announcement {
+    pub sender_id: i64,
+    /// In seconds since the epoch.
+    pub time: i64,
+    pub text: String,
+}
+
+/// The CSRF token the web APIs of QQ expect, derived from the `skey` cookie.
+fn bkn(cookies: &str) -> i64 {
+    let skey = cookies
+        .split(';')
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .find(|(name, _)| *name == "skey")
+        .map(|(_, value)| value)
+        .unwrap_or_default();
+
+    let mut hash: i64 = 5381;
+    for byte in skey.bytes() {
+        hash = hash.wrapping_add((hash << 5).wrapping_add(byte as i64));
+    }
+    hash & 0x7fffffff
+}
+
+/// The announcements are HTML-escaped, with the line breaks as `&#10;`.
+fn unescape(text: &str) -> String {
+    text.replace("&#10;", "\n")
+        .replace("&#13;", "")
+        .replace("&nbsp;", " ")
+        .replace("&#39;", "'")
+        .replace("&quot;", "\"")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+fn parse_announcements(body: &str) -> Option<Vec<Announcement>> {
+    let value: Value = serde_json::from_str(body).ok()?;
+    let feeds = match value.get("feeds") {
+        Some(feeds) => feeds.as_array()?,
+        // Groups without any announcement have no `feeds` at all.
+        None => return Some(Vec::new()),
+    };
+
+    let announcements = feeds
+        .iter()
+        .filter_map(|feed| {
+            Some(Announcement {
+                sender_id: feed.get("u")?.as_i64()?,
+                time: feed.get("pubt")?.as_i64()?,
+                text: unescape(feed.get("msg")?.get("text")?.as_str()?),
+            })
+        })
+        .collect();
+    Some(announcements)
+}
+
+/// Fetch the latest announcements of the group from the web API, as ricq
+/// has no packet for them.
+pub(crate) async fn fetch_announcements(
+    group_id: i64,
+) -> Result<Vec<Announcement>, Box<dyn Error + Send + Sync>> {
+    let client = CLIENT.get().unwrap();
+    let account = ACCOUNT.get().unwrap();
+    let cookies = client.get_cookies("qun.qq.com").await;
+
+    let url = format!(
+        "https://web.qun.qq.com/cgi-bin/announce/get_t_list?bkn={}&qid={}&ft=23&s=-1&n=20",
+        bkn(&cookies),
+        group_id
+    );
+    let body = reqwest::Client::new()
+        .get(url)
+        .header(
+            COOKIE,
+            format!("{}; uin=o{}; p_uin=o{}", cookies, account, account),
+        )
+        .send()
+        .await?
+        .text()
+        .await?;
+
+    parse_announcements(&body).ok_or_else(|| "Unexpected response of the announcements".into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bkn() {
+        assert_eq!(bkn("uin=o10000; skey="), 5381);
+        assert_eq!(bkn("skey=a"), 5381 * 33 + 97);
+    }
+
+    #[test]
+    fn test_parse_announcements() {
+        let body = r#"{"ec":0,"feeds":[{"u":10001,"pubt":1650000000,
+            "msg":{"text":"Hello&#10;&lt;world&gt; &amp; all"}}]}"#;
+        let announcements = parse_announcements(body).unwrap();
+        assert_eq!(announcements.len(), 1);
+        assert_eq!(announcements[0].sender_id, 10001);
+        assert_eq!(announcements[0].time, 1650000000);
+        assert_eq!(announcements[0].text, "Hello\n<world> & all");
+
+        assert!(parse_announcements(r#"{"ec":0}"#).unwrap().is_empty());
+        assert!(parse_announcements("not json").is_none());
+    }
+}
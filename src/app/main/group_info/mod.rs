@@ -0,0 +1,451 @@
+mod announcements;
+
+use relm4::{adw, gtk, Component, ComponentParts, ComponentSender, WidgetPlus};
+
+use adw::{prelude::*, ActionRow, Avatar, HeaderBar, Window};
+use gtk::gdk_pixbuf::Pixbuf;
+use gtk::glib::DateTime;
+use gtk::pango::EllipsizeMode;
+use gtk::{
+    Align, Box, Button, ButtonsType, Entry, Label, ListBox, MessageDialog, MessageType,
+    Orientation, Picture, ResponseType, ScrolledWindow, SelectionMode, Switch,
+};
+use ricq::structs::GroupMemberPermission;
+use tokio::task;
+
+use crate::db::fs::{download_group_avatar_file, get_group_avatar_path};
+use crate::db::sql::{
+    get_group_member_count, get_group_name, get_member_name, is_group_muted, set_group_muted,
+    update_member_card,
+};
+use crate::event::{publish, AppEvent};
+use crate::handler::{ACCOUNT, CLIENT};
+
+use super::MainMsg;
+use announcements::{fetch_announcements, Announcement};
+
+fn role_label(permission: &GroupMemberPermission) -> &'static str {
+    match permission {
+        GroupMemberPermission::Owner => "Owner",
+        GroupMemberPermission::Administrator => "Admin",
+        GroupMemberPermission::Member => "Member",
+    }
+}
+
+fn format_time(time: i64) -> String {
+    DateTime::from_unix_local(time)
+        .and_then(|time| time.format("%Y-%m-%d %H:%M"))
+        .map(|time| time.to_string())
+        .unwrap_or_default()
+}
+
+/// The details of a group we are in, with its announcements, and the
+/// actions on our membership.
+pub(crate) struct GroupInfoModel {
+    window: Window,
+    group_id: i64,
+    name: String,
+    is_busy: bool,
+}
+
+impl GroupInfoModel {
+    fn confirm_leave(&self, sender: &ComponentSender<Self>) {
+        let dialog = MessageDialog::builder()
+            .transient_for(&self.window)
+            .modal(true)
+            .message_type(MessageType::Question)
+            .buttons(ButtonsType::None)
+            .text(&format!("Leave {}?", self.name))
+            .secondary_text("You will need an invitation or an approval to join it again.")
+            .build();
+        dialog.add_button("Cancel", ResponseType::Cancel);
+        dialog
+            .add_button("Leave", ResponseType::Accept)
+            .add_css_class("destructive-action");
+        let sender = sender.clone();
+        dialog.connect_response(move |dialog, response| {
+            if response == ResponseType::Accept {
+                sender.input(GroupInfoMsg::Leave);
+            }
+            dialog.close();
+        });
+        dialog.present();
+    }
+}
+
+pub(crate) struct Payload {
+    pub window: Window,
+    pub group_id: i64,
+}
+
+#[derive(Debug)]
+pub(crate) enum GroupInfoMsg {
+    InfoLoaded {
+        owner_id: i64,
+        member_count: i64,
+    },
+    SelfLoaded {
+        card_name: String,
+        permission: GroupMemberPermission,
+    },
+    AnnouncementsLoaded(Vec<Announcement>),
+    AnnouncementsFailed(String),
+    SaveCard(String),
+    CardSaved(String),
+    SetMuted(bool),
+    ConfirmLeave,
+    Leave,
+    Left,
+    Failed(String),
+}
+
+async fn load_info(group_id: i64, sender: ComponentSender<GroupInfoModel>) {
+    let client = CLIENT.get().unwrap();
+    match client.get_group_info(group_id).await {
+        Ok(Some(info)) => sender.input(GroupInfoMsg::InfoLoaded {
+            owner_id: info.owner_uin,
+            member_count: info.member_count as i64,
+        }),
+        Ok(None) => sender.input(GroupInfoMsg::Failed("The group does not exist".to_string())),
+        Err(err) => sender.input(GroupInfoMsg::Failed(format!(
+            "Failed to load the group info: {}",
+            err
+        ))),
+    }
+
+    let account = *ACCOUNT.get().unwrap();
+    match client.get_group_member_info(group_id, account).await {
+        Ok(info) => sender.input(GroupInfoMsg::SelfLoaded {
+            card_name: info.card_name,
+            permission: info.permission,
+        }),
+        Err(err) => log::warn!("Failed to get our member info in {}: {}", group_id, err),
+    }
+
+    match fetch_announcements(group_id).await {
+        Ok(announcements) => sender.input(GroupInfoMsg::AnnouncementsLoaded(announcements)),
+        Err(err) => sender.input(GroupInfoMsg::AnnouncementsFailed(err.to_string())),
+    }
+}
+
+async fn save_card(group_id: i64, card_name: String, sender: ComponentSender<GroupInfoModel>) {
+    let client = CLIENT.get().unwrap();
+    let account = *ACCOUNT.get().unwrap();
+    match client
+        .edit_group_member_card(group_id, account, card_name.clone())
+        .await
+    {
+        Ok(()) => sender.input(GroupInfoMsg::CardSaved(card_name)),
+        Err(err) => sender.input(GroupInfoMsg::Failed(format!(
+            "Failed to change the group card: {}",
+            err
+        ))),
+    }
+}
+
+async fn leave_group(group_id: i64, sender: ComponentSender<GroupInfoModel>) {
+    let client = CLIENT.get().unwrap();
+    match client.group_quit(group_id).await {
+        Ok(()) => sender.input(GroupInfoMsg::Left),
+        Err(err) => sender.input(GroupInfoMsg::Failed(format!(
+            "Failed to leave the group: {}",
+            err
+        ))),
+    }
+}
+
+fn announcement_row(group_id: i64, announcement: &Announcement) -> Box {
+    let sender_name = get_member_name(group_id, announcement.sender_id, "");
+    relm4::view! {
+        row = Box {
+            set_orientation: Orientation::Vertical,
+            set_spacing: 4,
+            set_margin_all: 12,
+            Label {
+                set_xalign: 0.0,
+                set_wrap: true,
+                set_selectable: true,
+                set_text: &announcement.text,
+            },
+            Label {
+                set_xalign: 0.0,
+                set_ellipsize: EllipsizeMode::End,
+                set_text: &format!("{} · {}", sender_name, format_time(announcement.time)),
+                set_css_classes: &["caption", "dim-label"],
+            },
+        }
+    }
+    row
+}
+
+#[derive(Debug)]
+pub(crate) struct GroupInfoWidgets {
+    owner_row: ActionRow,
+    member_count_row: ActionRow,
+    role_row: ActionRow,
+    card_entry: Entry,
+    leave_button: Button,
+    announcements_list: ListBox,
+    announcements_status: Label,
+}
+
+impl Component for GroupInfoModel {
+    type Input = GroupInfoMsg;
+    type Output = MainMsg;
+    type InitParams = Payload;
+    type Widgets = GroupInfoWidgets;
+    type Root = Box;
+    type CommandOutput = ();
+
+    fn init_root() -> Self::Root {
+        Box::new(Orientation::Vertical, 0)
+    }
+
+    fn init(
+        params: Self::InitParams,
+        root: &Self::Root,
+        sender: &ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let group_id = params.group_id;
+        let model = GroupInfoModel {
+            window: params.window,
+            group_id,
+            name: get_group_name(group_id),
+            is_busy: false,
+        };
+        let member_count = get_group_member_count(group_id)
+            .map(|count| count.to_string())
+            .unwrap_or_default();
+
+        relm4::view! {
+            header_bar = HeaderBar {
+                set_title_widget = Some(&Label) {
+                    set_label: "Group Info",
+                },
+            }
+        }
+
+        relm4::view! {
+            scrolled_window = ScrolledWindow {
+                set_vexpand: true,
+                set_child = Some(&Box) {
+                    set_orientation: Orientation::Vertical,
+                    set_spacing: 12,
+                    set_margin_all: 24,
+                    #[name = "avatar"]
+                    Avatar {
+                        set_size: 96,
+                        set_text: Some(&model.name),
+                        set_show_initials: true,
+                        set_halign: Align::Center,
+                    },
+                    Label {
+                        set_label: &model.name,
+                        set_selectable: true,
+                        set_ellipsize: EllipsizeMode::End,
+                        add_css_class: "title-2",
+                    },
+                    Label {
+                        set_label: &group_id.to_string(),
+                        set_selectable: true,
+                        set_css_classes: &["caption", "dim-label"],
+                    },
+                    ListBox {
+                        set_selection_mode: SelectionMode::None,
+                        add_css_class: "boxed-list",
+                        append: owner_row = &ActionRow {
+                            set_title: "Owner",
+                        },
+                        append: member_count_row = &ActionRow {
+                            set_title: "Members",
+                            set_subtitle: &member_count,
+                        },
+                        append: role_row = &ActionRow {
+                            set_title: "Your Role",
+                        },
+                        append = &ActionRow {
+                            set_title: "Your Card",
+                            add_suffix: card_entry = &Entry {
+                                set_valign: Align::Center,
+                                set_placeholder_text: Some("No card"),
+                                connect_activate[sender] => move |entry| {
+                                    sender.input(GroupInfoMsg::SaveCard(entry.buffer().text()));
+                                },
+                            },
+                        },
+                        append = &ActionRow {
+                            set_title: "Mute Notifications",
+                            add_suffix = &Switch {
+                                set_valign: Align::Center,
+                                set_active: is_group_muted(group_id),
+                                connect_state_set[sender] => move |_, state| {
+                                    sender.input(GroupInfoMsg::SetMuted(state));
+                                    gtk::Inhibit(false)
+                                },
+                            },
+                        },
+                    },
+                    Label {
+                        set_label: "Announcements",
+                        set_halign: Align::Start,
+                        add_css_class: "heading",
+                    },
+                    #[name = "announcements_status"]
+                    Label {
+                        set_label: "Loading…",
+                        add_css_class: "dim-label",
+                    },
+                    #[name = "announcements_list"]
+                    ListBox {
+                        set_selection_mode: SelectionMode::None,
+                        set_visible: false,
+                        add_css_class: "boxed-list",
+                    },
+                    #[name = "leave_button"]
+                    Button {
+                        set_label: "Leave Group",
+                        set_halign: Align::Center,
+                        add_css_class: "destructive-action",
+                        connect_clicked[sender] => move |_| {
+                            sender.input(GroupInfoMsg::ConfirmLeave);
+                        },
+                    },
+                }
+            }
+        }
+
+        let avatar_path = get_group_avatar_path(group_id);
+        if avatar_path.exists() {
+            if let Ok(pixbuf) = Pixbuf::from_file_at_size(avatar_path, 96, 96) {
+                let image = Picture::for_pixbuf(&pixbuf);
+                if let Some(paintable) = image.paintable() {
+                    avatar.set_custom_image(Some(&paintable));
+                }
+            }
+        } else {
+            task::spawn(download_group_avatar_file(group_id));
+        }
+
+        root.append(&header_bar);
+        root.append(&scrolled_window);
+
+        task::spawn(load_info(group_id, sender.clone()));
+
+        ComponentParts {
+            model,
+            widgets: GroupInfoWidgets {
+                owner_row,
+                member_count_row,
+                role_row,
+                card_entry,
+                leave_button,
+                announcements_list,
+                announcements_status,
+            },
+        }
+    }
+
+    fn update_with_view(
+        &mut self,
+        widgets: &mut Self::Widgets,
+        msg: Self::Input,
+        sender: &ComponentSender<Self>,
+    ) {
+        use GroupInfoMsg::*;
+        match msg {
+            InfoLoaded {
+                owner_id,
+                member_count,
+            } => {
+                let owner = get_member_name(self.group_id, owner_id, "");
+                widgets
+                    .owner_row
+                    .set_subtitle(&format!("{} ({})", owner, owner_id));
+                widgets
+                    .member_count_row
+                    .set_subtitle(&member_count.to_string());
+            }
+            SelfLoaded {
+                card_name,
+                permission,
+            } => {
+                widgets.role_row.set_subtitle(role_label(&permission));
+                widgets.card_entry.set_text(&card_name);
+            }
+            AnnouncementsLoaded(announcements) => {
+                if announcements.is_empty() {
+                    widgets.announcements_status.set_label("No announcements");
+                } else {
+                    widgets.announcements_status.set_visible(false);
+                    widgets.announcements_list.set_visible(true);
+                    for announcement in announcements.iter() {
+                        widgets
+                            .announcements_list
+                            .append(&announcement_row(self.group_id, announcement));
+                    }
+                }
+            }
+            AnnouncementsFailed(err) => {
+                log::warn!(
+                    "Failed to load the announcements of {}: {}",
+                    self.group_id,
+                    err
+                );
+                widgets
+                    .announcements_status
+                    .set_label("Failed to load the announcements");
+            }
+            SaveCard(card_name) => {
+                if !self.is_busy {
+                    self.is_busy = true;
+                    widgets.card_entry.set_sensitive(false);
+                    task::spawn(save_card(self.group_id, card_name, sender.clone()));
+                }
+            }
+            CardSaved(card_name) => {
+                self.is_busy = false;
+                widgets.card_entry.set_sensitive(true);
+                let account = *ACCOUNT.get().unwrap();
+                if let Err(err) = update_member_card(self.group_id, account, &card_name) {
+                    log::warn!("Failed to save our card in {}: {}", self.group_id, err);
+                }
+                publish(AppEvent::MemberCardChanged {
+                    group_id: self.group_id,
+                    member_id: account,
+                    name: get_member_name(self.group_id, account, &card_name),
+                });
+                sender.output(MainMsg::PushToast("Changed the group card.".to_string()));
+            }
+            SetMuted(muted) => {
+                if let Err(err) = set_group_muted(self.group_id, muted) {
+                    sender.output(MainMsg::PushToast(err.to_string()));
+                }
+            }
+            ConfirmLeave => self.confirm_leave(sender),
+            Leave => {
+                if !self.is_busy {
+                    self.is_busy = true;
+                    widgets.leave_button.set_sensitive(false);
+                    task::spawn(leave_group(self.group_id, sender.clone()));
+                }
+            }
+            Left => {
+                // The contact sync removes the group, and the chatroom turns
+                // read-only.
+                publish(AppEvent::GroupLeave {
+                    group_id: self.group_id,
+                    member_id: *ACCOUNT.get().unwrap(),
+                    operator_id: None,
+                });
+                sender.output(MainMsg::PushToast(format!("Left {}.", self.name)));
+                self.window.close();
+            }
+            Failed(err) => {
+                self.is_busy = false;
+                widgets.card_entry.set_sensitive(true);
+                widgets.leave_button.set_sensitive(true);
+                sender.output(MainMsg::PushToast(err));
+            }
+        }
+    }
+}
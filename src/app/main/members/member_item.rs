@@ -0,0 +1,109 @@
+use relm4::{adw, gtk};
+
+use adw::{prelude::*, Avatar};
+use gtk::gdk_pixbuf::Pixbuf;
+use gtk::glib::DateTime;
+use gtk::pango::EllipsizeMode;
+use gtk::{Align, Box, Label, Orientation, Picture};
+use ricq::structs::GroupMemberPermission;
+
+use tokio::task;
+
+use crate::db::fs::{download_user_avatar_file, get_user_avatar_path};
+use crate::db::sql::GroupMember;
+
+fn format_last_speak_time(time: i64) -> String {
+    if time <= 0 {
+        return "Never spoke".to_string();
+    }
+    DateTime::from_unix_local(time)
+        .and_then(|time| time.format("Last spoke %Y-%m-%d %H:%M"))
+        .map(|time| time.to_string())
+        .unwrap_or_default()
+}
+
+fn role_label(permission: &GroupMemberPermission) -> Option<&'static str> {
+    match permission {
+        GroupMemberPermission::Owner => Some("Owner"),
+        GroupMemberPermission::Administrator => Some("Admin"),
+        GroupMemberPermission::Member => None,
+    }
+}
+
+/// Build the widgets of `member` into `root`, the child of the list item it
+/// is bound to. Only the visible members are bound, so their avatars are
+/// loaded lazily.
+pub(super) fn bind_member(member: &GroupMember, root: &Box) {
+    let role = role_label(&member.permission);
+
+    relm4::view! {
+        item = Box {
+            set_margin_top: 4,
+            set_margin_bottom: 4,
+            #[name = "avatar"]
+            Avatar {
+                set_text: Some(member.display_name()),
+                set_show_initials: true,
+                set_size: 32,
+                set_margin_end: 8,
+                set_valign: Align::Start,
+            },
+            Box {
+                set_orientation: Orientation::Vertical,
+                set_hexpand: true,
+                set_spacing: 2,
+                Box {
+                    set_spacing: 4,
+                    Label {
+                        set_xalign: 0.0,
+                        set_text: member.display_name(),
+                        set_ellipsize: EllipsizeMode::End,
+                        add_css_class: "heading",
+                    },
+                    Label {
+                        set_text: role.unwrap_or_default(),
+                        set_visible: role.is_some(),
+                        set_css_classes: &["caption", "accent"],
+                    },
+                    Label {
+                        set_text: &member.special_title,
+                        set_visible: !member.special_title.is_empty(),
+                        set_ellipsize: EllipsizeMode::End,
+                        set_css_classes: &["caption", "warning"],
+                    },
+                },
+                Label {
+                    set_xalign: 0.0,
+                    set_text: &format!("{} ({})", member.nickname, member.uin),
+                    set_ellipsize: EllipsizeMode::End,
+                    set_css_classes: &["caption", "dim-label"],
+                },
+                Label {
+                    set_xalign: 0.0,
+                    set_text: &format_last_speak_time(member.last_speak_time),
+                    set_css_classes: &["caption", "dim-label"],
+                },
+            },
+        }
+    }
+
+    let avatar_path = get_user_avatar_path(member.uin);
+    if avatar_path.exists() {
+        if let Ok(pixbuf) = Pixbuf::from_file_at_size(avatar_path, 32, 32) {
+            let image = Picture::for_pixbuf(&pixbuf);
+            if let Some(paintable) = image.paintable() {
+                avatar.set_custom_image(Some(&paintable));
+            }
+        }
+    } else {
+        task::spawn(download_user_avatar_file(member.uin));
+    }
+
+    root.append(&item);
+}
+
+pub(super) fn unbind_member(root: &Box) {
+    while let Some(child) = root.first_child() {
+        root.remove(&child);
+    }
+}
@@ -0,0 +1,209 @@
+mod member_item;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use relm4::{adw, gtk, ComponentParts, ComponentSender, SimpleComponent, WidgetPlus};
+
+use adw::prelude::*;
+use gtk::glib::{self, BoxedAnyObject};
+use gtk::{
+    gio, Box, Button, CustomFilter, Entry, EntryIconPosition, FilterChange, FilterListModel,
+    ListItem, ListView, NoSelection, Orientation, ScrolledWindow, SignalListItemFactory,
+};
+use tokio::task;
+
+use super::MainMsg;
+use crate::db::sql::{get_group_members, refresh_group_members, GroupMember};
+use member_item::{bind_member, unbind_member};
+
+/// The side panel listing the members of the group in the chatroom. It
+/// shows the cached members at once, and refreshes them in the background.
+#[derive(Debug)]
+pub(crate) struct MembersModel {
+    group_id: Option<i64>,
+    /// All the cached members of the group as `GroupMember`s wrapped in
+    /// `BoxedAnyObject`s, which are shown through `filter`.
+    members: gio::ListStore,
+    filter: CustomFilter,
+    /// The lowercased keyword the members are filtered by.
+    keyword: Rc<RefCell<String>>,
+    is_refresh_button_enabled: bool,
+}
+
+impl MembersModel {
+    fn load_cached(&mut self) {
+        let group_id = match self.group_id {
+            Some(group_id) => group_id,
+            None => return,
+        };
+        let members: Vec<glib::Object> = get_group_members(group_id)
+            .unwrap_or_else(|err| {
+                log::warn!("Failed to load the members of {}: {}", group_id, err);
+                Vec::new()
+            })
+            .into_iter()
+            .map(|member| BoxedAnyObject::new(member).upcast())
+            .collect();
+        self.members.splice(0, self.members.n_items(), &members);
+    }
+}
+
+fn matches_keyword(member: &GroupMember, keyword: &str) -> bool {
+    keyword.is_empty()
+        || member.card_name.to_lowercase().contains(keyword)
+        || member.nickname.to_lowercase().contains(keyword)
+        || member.uin.to_string().contains(keyword)
+}
+
+fn bound_member(list_item: &ListItem) -> Option<(BoxedAnyObject, Box)> {
+    let member = list_item.item()?.downcast::<BoxedAnyObject>().ok()?;
+    let root = list_item.child()?.downcast::<Box>().ok()?;
+    Some((member, root))
+}
+
+/// The widgets of a member are built when it is bound to a list item, so
+/// only the visible members are realized.
+fn member_factory() -> SignalListItemFactory {
+    let factory = SignalListItemFactory::new();
+    factory.connect_setup(|_, list_item| {
+        list_item.set_activatable(false);
+        list_item.set_child(Some(&Box::default()));
+    });
+    factory.connect_bind(|_, list_item| {
+        if let Some((member, root)) = bound_member(list_item) {
+            bind_member(&member.borrow::<GroupMember>(), &root);
+        }
+    });
+    factory.connect_unbind(|_, list_item| {
+        if let Some((_, root)) = bound_member(list_item) {
+            unbind_member(&root);
+        }
+    });
+    factory
+}
+
+async fn refresh_members(group_id: i64, sender: ComponentSender<MembersModel>) {
+    match refresh_group_members(group_id).await {
+        Ok(()) => sender.input(MembersMsg::Refreshed(group_id)),
+        Err(err) => sender.input(MembersMsg::Failed(err.to_string())),
+    }
+}
+
+#[derive(Debug)]
+pub enum MembersMsg {
+    /// Show the members of the group.
+    Load(i64),
+    Refresh,
+    Refreshed(i64),
+    Failed(String),
+    Search(String),
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for MembersModel {
+    type Input = MembersMsg;
+    type Output = MainMsg;
+    type Widgets = MembersWidgets;
+    type InitParams = ();
+
+    view! {
+        #[root]
+        members = Box {
+            set_orientation: Orientation::Vertical,
+            set_width_request: 280,
+            Box {
+                set_margin_all: 8,
+                Button {
+                    #[watch]
+                    set_sensitive: model.is_refresh_button_enabled,
+                    set_tooltip_text: Some("Refresh members"),
+                    set_icon_name: "view-refresh-symbolic",
+                    set_margin_end: 8,
+                    connect_clicked[sender] => move |_| {
+                        sender.input(MembersMsg::Refresh);
+                    },
+                },
+                Entry {
+                    set_hexpand: true,
+                    set_icon_from_icon_name: (EntryIconPosition::Secondary, Some("system-search-symbolic")),
+                    set_placeholder_text: Some("Search in members..."),
+                    connect_changed[sender] => move |entry| {
+                        sender.input(MembersMsg::Search(entry.buffer().text()));
+                    },
+                },
+            },
+            #[name = "scrolled_window"]
+            ScrolledWindow {
+                set_vexpand: true,
+            }
+        }
+    }
+
+    fn init(
+        _init_params: (),
+        root: &Self::Root,
+        sender: &ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let widgets = view_output!();
+
+        let members = gio::ListStore::new(BoxedAnyObject::static_type());
+        let keyword = Rc::new(RefCell::new(String::new()));
+        let filter = CustomFilter::new(glib::clone!(@strong keyword => move |member| {
+            let member = member.downcast_ref::<BoxedAnyObject>().unwrap();
+            let member = member.borrow::<GroupMember>();
+            matches_keyword(&member, &keyword.borrow())
+        }));
+        let filtered = FilterListModel::new(Some(&members), Some(&filter));
+        let list_view = ListView::new(
+            Some(&NoSelection::new(Some(&filtered))),
+            Some(&member_factory()),
+        );
+        list_view.set_css_classes(&["navigation-sidebar"]);
+        widgets.scrolled_window.set_child(Some(&list_view));
+
+        let model = MembersModel {
+            group_id: None,
+            members,
+            filter,
+            keyword,
+            is_refresh_button_enabled: true,
+        };
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: MembersMsg, sender: &ComponentSender<Self>) {
+        use MembersMsg::*;
+        match msg {
+            Load(group_id) => {
+                if self.group_id != Some(group_id) {
+                    self.group_id = Some(group_id);
+                    self.load_cached();
+                    sender.input(Refresh);
+                }
+            }
+            Refresh => {
+                if let Some(group_id) = self.group_id {
+                    self.is_refresh_button_enabled = false;
+                    task::spawn(refresh_members(group_id, sender.clone()));
+                }
+            }
+            Refreshed(group_id) => {
+                // Another group may have been loaded in the meantime.
+                if self.group_id == Some(group_id) {
+                    self.load_cached();
+                }
+                self.is_refresh_button_enabled = true;
+            }
+            Failed(err) => {
+                self.is_refresh_button_enabled = true;
+                sender.output(MainMsg::PushToast(err));
+            }
+            Search(keyword) => {
+                *self.keyword.borrow_mut() = keyword.to_lowercase();
+                self.filter.changed(FilterChange::Different);
+            }
+        }
+    }
+}
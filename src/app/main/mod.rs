@@ -1,37 +1,65 @@
+mod add_friend;
 mod chatroom;
+mod forward_picker;
+mod group_info;
+mod members;
+mod profile;
 mod sidebar;
+mod switcher;
 
-use std::collections::VecDeque;
+use std::collections::HashMap;
 
-use once_cell::sync::OnceCell;
 use relm4::factory::FactoryVecDeque;
 use relm4::{
     adw, component::Controller, gtk, Component, ComponentController, ComponentParts,
     ComponentSender,
 };
 
-use adw::{prelude::*, HeaderBar, Leaflet, Toast, ToastOverlay};
-use gtk::{Align, Box, Label, MenuButton, Orientation, Separator, Stack};
+use adw::{prelude::*, HeaderBar, Leaflet, Toast, ToastOverlay, Window};
+use gtk::glib::clone;
+use gtk::{
+    Align, Box, Button, CallbackAction, Label, MenuButton, Orientation, Revealer,
+    RevealerTransitionType, Separator, Shortcut, ShortcutController, ShortcutScope,
+    ShortcutTrigger, Stack, ToggleButton,
+};
 
-use chatroom::{Chatroom, ChatroomInitParams};
+use add_friend::{AddFriendModel, Payload as AddFriendPayload};
+use chatroom::{chatroom_name, Chatroom, ChatroomInitParams};
+use forward_picker::{ForwardPickerModel, Payload as ForwardPickerPayload};
+use group_info::{GroupInfoModel, Payload as GroupInfoPayload};
+use members::{MembersModel, MembersMsg};
+use profile::{Payload as ProfilePayload, ProfileModel};
 use sidebar::{SidebarModel, SidebarMsg};
+use switcher::{Payload as SwitcherPayload, SwitcherModel};
 
-use crate::db::sql::{get_db, get_group_name};
-use crate::utils::message::Message;
+use crate::connection::ConnectionState;
+use crate::db::sql::{find_friend_remark, get_db, get_group_member_count, get_group_name};
+use crate::event::{self, AppEvent};
+use crate::global::WINDOW;
+use crate::handler::ACCOUNT;
+use crate::utils::message::{describe_group_event, Message};
 
-pub(crate) static MAIN_SENDER: OnceCell<ComponentSender<MainPageModel>> = OnceCell::new();
+pub(crate) use crate::db::sql::ChatKind;
 
 #[derive(Debug)]
 pub(crate) struct MainPageModel {
     sidebar: Controller<SidebarModel>,
+    members: Controller<MembersModel>,
     chatrooms: FactoryVecDeque<Stack, Chatroom, MainMsg>,
+    /// The group of the visible chatroom, if it is a group chat.
+    current_group: Option<i64>,
+    /// The user of the visible chatroom, if it is a friend or temp chat.
+    current_user: Option<i64>,
+    /// The nicknames of the strangers in temp chats, who are not in the
+    /// friends table.
+    temp_names: HashMap<i64, String>,
 }
 
 impl MainPageModel {
-    fn is_item_in_list(&self, account: i64, is_group: bool) -> bool {
+    fn is_item_in_list(&self, account: i64, kind: ChatKind) -> bool {
         for i in 0..self.chatrooms.len() {
             let chatroom = self.chatrooms.get(i);
-            if chatroom.account == account && chatroom.is_group == is_group {
+            if chatroom.account == account && chatroom.kind == kind {
                 return true;
             }
         }
@@ -39,46 +67,189 @@ impl MainPageModel {
         false
     }
 
-    fn insert_chatroom(&mut self, account: i64, is_group: bool) {
-        // TODO: Get history messages
-        let messages = VecDeque::new();
-        self.chatrooms.push_front(ChatroomInitParams {
-            account,
-            is_group,
-            messages,
-        });
+    /// The new chatroom loads its history by itself, which includes the
+    /// message that causes it to be inserted, if any.
+    fn insert_chatroom(&mut self, account: i64, kind: ChatKind) {
+        self.chatrooms
+            .push_front(ChatroomInitParams { account, kind });
 
         self.chatrooms.render_changes();
     }
 
-    fn push_friend_message(&mut self, friend_id: i64, message: Message) {
+    fn push_message(&mut self, account: i64, kind: ChatKind, message: Message) {
         for i in 0..self.chatrooms.len() {
             let mut chatroom = self.chatrooms.get_mut(i);
-            if chatroom.account == friend_id && !chatroom.is_group {
+            if chatroom.account == account && chatroom.kind == kind {
                 chatroom.push_message(message);
                 break;
             }
         }
     }
 
-    fn push_group_message(&mut self, group_id: i64, message: Message) {
+    /// Show the members and group info buttons only in group chats, and the
+    /// members of the current group if the panel is open.
+    fn set_current_group(&mut self, widgets: &MainPageWidgets, group_id: Option<i64>) {
+        self.current_group = group_id;
+        widgets.members_button.set_visible(group_id.is_some());
+        widgets.group_info_button.set_visible(group_id.is_some());
+        match group_id {
+            Some(group_id) if widgets.members_revealer.reveals_child() => {
+                self.members.emit(MembersMsg::Load(group_id));
+            }
+            Some(_) => {}
+            None => widgets.members_button.set_active(false),
+        }
+    }
+
+    /// Show the profile button only in friend and temp chats.
+    fn set_current_user(&mut self, widgets: &MainPageWidgets, user_id: Option<i64>) {
+        self.current_user = user_id;
+        widgets.profile_button.set_visible(user_id.is_some());
+    }
+
+    /// The title and subtitle of the temp chat with `user_id`.
+    fn temp_chat_title(&self, user_id: i64, group_id: i64) -> (String, String) {
+        let title = self
+            .temp_names
+            .get(&user_id)
+            .cloned()
+            .unwrap_or_else(|| user_id.to_string());
+        let subtitle = format!("via {} ({})", get_group_name(group_id), user_id);
+        (title, subtitle)
+    }
+
+    fn push_group_notice(&mut self, group_id: i64, notice: String) {
         for i in 0..self.chatrooms.len() {
             let mut chatroom = self.chatrooms.get_mut(i);
-            if chatroom.account == group_id && chatroom.is_group {
-                chatroom.push_message(message);
+            if chatroom.account == group_id && chatroom.kind == ChatKind::Group {
+                chatroom.push_notice(notice);
                 break;
             }
         }
     }
+
+    fn set_group_read_only(&mut self, group_id: i64) {
+        for i in 0..self.chatrooms.len() {
+            let mut chatroom = self.chatrooms.get_mut(i);
+            if chatroom.account == group_id && chatroom.kind == ChatKind::Group {
+                chatroom.set_read_only();
+                break;
+            }
+        }
+    }
+
+    /// Show the chatroom of `account`, with its title and the buttons of the
+    /// chat in the header bar.
+    fn focus_chatroom(&mut self, widgets: &MainPageWidgets, account: i64, kind: ChatKind) {
+        let child_name = &chatroom_name(account, kind);
+        widgets.chatroom_stack.set_visible_child_name(child_name);
+        self.set_current_group(widgets, kind.is_group().then(|| account));
+        self.set_current_user(widgets, (!kind.is_group()).then(|| account));
+
+        if let ChatKind::Temp { group_id } = kind {
+            let (title, subtitle) = self.temp_chat_title(account, group_id);
+            widgets.chatroom_title.set_label(&title);
+            widgets.chatroom_subtitle.set_label(&subtitle);
+        } else if kind.is_group() {
+            let group_name: String = get_group_name(account);
+            let title = group_name;
+            let subtitle = group_subtitle(account);
+            widgets.chatroom_title.set_label(&title);
+            widgets.chatroom_subtitle.set_label(&subtitle);
+        } else {
+            let (user_name, user_remark): (String, String) = get_db()
+                .query_row(
+                    "Select name, remark from friends where id=?1",
+                    [account],
+                    |row| Ok((row.get(0).unwrap(), row.get(1).unwrap())),
+                )
+                .unwrap();
+            let title = &user_name;
+            let subtitle = format!("{} ({})", user_remark, account);
+            widgets.chatroom_title.set_label(title);
+            widgets.chatroom_subtitle.set_label(&subtitle);
+        }
+    }
+
+    /// Show a received or sent `message` in the chatroom of `account`, which
+    /// is created with its sidebar item if it is not there yet.
+    fn receive_message(
+        &mut self,
+        widgets: &MainPageWidgets,
+        account: i64,
+        kind: ChatKind,
+        message: Message,
+    ) {
+        use SidebarMsg::*;
+        if self.is_item_in_list(account, kind) {
+            self.sidebar
+                .sender()
+                .send(UpdateChatItem(account, kind, message.text()));
+            self.push_message(account, kind, message);
+            return;
+        }
+
+        self.sidebar
+            .sender()
+            .send(InsertChatItem(account, kind, message.text()));
+        if let ChatKind::Temp { group_id } = kind {
+            let name = self.temp_chat_title(account, group_id).0;
+            self.sidebar
+                .sender()
+                .send(RenameChatItem(account, kind, name));
+        }
+        self.insert_chatroom(account, kind);
+        // 当所插入的 chatroom 为唯一的一个 chatroom 时，将其设为焦点，
+        // 以触发自动更新 chatroom 的标题与副标题。
+        if self.chatrooms.len() == 1 {
+            self.focus_chatroom(widgets, account, kind);
+        }
+    }
 }
 
 #[derive(Debug)]
 pub(crate) enum MainMsg {
     WindowFolded,
-    GroupMessage { group_id: i64, message: Message },
-    FriendMessage { friend_id: i64, message: Message },
-    SelectChatroom(i64, bool),
+    GroupMessage {
+        group_id: i64,
+        message: Message,
+    },
+    FriendMessage {
+        friend_id: i64,
+        message: Message,
+    },
+    /// Group system events, such as `NewMember` and `GroupNameUpdate`.
+    GroupEvent(AppEvent),
+    /// A message in a temp chat with `user_id`, either sent or received.
+    TempMessage {
+        group_id: i64,
+        user_id: i64,
+        message: Message,
+    },
+    SelectChatroom(i64, ChatKind),
+    ForwardMessages(Vec<Message>),
     PushToast(String),
+    ConnectionChanged(ConnectionState),
+    ToggleMembers(bool),
+    ShowProfile(i64),
+    /// Show the profile of the user in the visible chatroom.
+    ShowCurrentProfile,
+    /// Show the details of the group in the visible chatroom.
+    ShowGroupInfo,
+    /// Open the quick switcher, bound to Ctrl+K.
+    OpenSwitcher,
+    ShowAddFriend,
+    /// Someone became our friend, maybe by accepting our request.
+    FriendAdded(i64, String),
+    /// The remark of the friend has been changed.
+    RenameFriend(i64, String),
+}
+
+fn group_subtitle(group_id: i64) -> String {
+    match get_group_member_count(group_id) {
+        Some(count) => format!("{} · {} members", group_id, count),
+        None => group_id.to_string(),
+    }
 }
 
 pub struct MainPageWidgets {
@@ -88,6 +259,10 @@ pub struct MainPageWidgets {
     chatroom_title: Label,
     chatroom_subtitle: Label,
     chatroom_stack: Stack,
+    members_button: ToggleButton,
+    members_revealer: Revealer,
+    profile_button: Button,
+    group_info_button: Button,
 }
 
 relm4::new_action_group!(WindowActionGroup, "menu");
@@ -111,13 +286,42 @@ impl Component for MainPageModel {
         root: &Self::Root,
         sender: &ComponentSender<Self>,
     ) -> ComponentParts<Self> {
-        MAIN_SENDER
-            .set(sender.clone())
-            .expect("failed to initialize main sender");
+        event::forward(sender.input_sender().clone(), |event| match event {
+            AppEvent::GroupMessage { group_id, message } => {
+                Some(MainMsg::GroupMessage { group_id, message })
+            }
+            AppEvent::FriendMessage { friend_id, message } => {
+                Some(MainMsg::FriendMessage { friend_id, message })
+            }
+            AppEvent::GroupTempMessage { group_id, message } => Some(MainMsg::TempMessage {
+                group_id,
+                user_id: message.sender_id,
+                message,
+            }),
+            event @ (AppEvent::NewMember { .. }
+            | AppEvent::GroupLeave { .. }
+            | AppEvent::GroupMute { .. }
+            | AppEvent::GroupNameUpdate { .. }
+            | AppEvent::GroupDisband { .. }
+            | AppEvent::MemberPermissionChange { .. }
+            | AppEvent::MemberCardChanged { .. }) => Some(MainMsg::GroupEvent(event)),
+            AppEvent::FriendRemarkChanged { friend_id, remark } => {
+                Some(MainMsg::RenameFriend(friend_id, remark))
+            }
+            AppEvent::NewFriend {
+                friend_id,
+                nickname,
+            } => Some(MainMsg::FriendAdded(friend_id, nickname)),
+            AppEvent::ConnectionChanged { state, .. } => Some(MainMsg::ConnectionChanged(state)),
+            _ => None,
+        });
 
         let sidebar_controller = SidebarModel::builder()
             .launch(())
             .forward(&sender.input, |message| message);
+        let members_controller = MembersModel::builder()
+            .launch(())
+            .forward(&sender.input, |message| message);
 
         relm4::menu! {
             main_menu: {
@@ -153,9 +357,45 @@ impl Component for MainPageModel {
                         pack_end = &MenuButton {
                             set_icon_name: "menu-symbolic",
                             set_menu_model: Some(&main_menu),
-                        }
+                        },
+                        pack_end: profile_button = &Button {
+                            set_icon_name: "avatar-default-symbolic",
+                            set_tooltip_text: Some("Profile"),
+                            set_visible: false,
+                            connect_clicked[sender] => move |_| {
+                                sender.input(MainMsg::ShowCurrentProfile);
+                            },
+                        },
+                        pack_end: group_info_button = &Button {
+                            set_icon_name: "dialog-information-symbolic",
+                            set_tooltip_text: Some("Group Info"),
+                            set_visible: false,
+                            connect_clicked[sender] => move |_| {
+                                sender.input(MainMsg::ShowGroupInfo);
+                            },
+                        },
+                        pack_end: members_button = &ToggleButton {
+                            set_icon_name: "system-users-symbolic",
+                            set_tooltip_text: Some("Members"),
+                            set_visible: false,
+                            connect_toggled[sender] => move |button| {
+                                sender.input(MainMsg::ToggleMembers(button.is_active()));
+                            },
+                        },
+                    },
+                    Box {
+                        set_vexpand: true,
+                        append: chatroom_stack = &Stack {
+                            set_hexpand: true,
+                        },
+                        append: members_revealer = &Revealer {
+                            set_transition_type: RevealerTransitionType::SlideLeft,
+                            set_child = Some(&Box) {
+                                append = &Separator::new(Orientation::Vertical),
+                                append: members_controller.widget(),
+                            },
+                        },
                     },
-                    append: chatroom_stack = &Stack {},
                 },
                 connect_folded_notify[sender] => move |leaflet| {
                     if leaflet.is_folded() {
@@ -167,13 +407,29 @@ impl Component for MainPageModel {
 
         root.set_child(Some(&main_page));
 
+        let switcher_shortcut = Shortcut::new(
+            ShortcutTrigger::parse_string("<Control>k").as_ref(),
+            Some(&CallbackAction::new(clone!(@strong sender => move |_, _| {
+                sender.input(MainMsg::OpenSwitcher);
+                true
+            }))),
+        );
+        let shortcut_controller = ShortcutController::new();
+        shortcut_controller.set_scope(ShortcutScope::Global);
+        shortcut_controller.add_shortcut(&switcher_shortcut);
+        root.add_controller(&shortcut_controller);
+
         let chatrooms: FactoryVecDeque<Stack, Chatroom, MainMsg> =
             FactoryVecDeque::new(chatroom_stack.clone(), &sender.input);
 
         ComponentParts {
             model: MainPageModel {
                 sidebar: sidebar_controller,
+                members: members_controller,
                 chatrooms,
+                current_group: None,
+                current_user: None,
+                temp_names: HashMap::new(),
             },
             widgets: MainPageWidgets {
                 root: root.clone(),
@@ -182,6 +438,10 @@ impl Component for MainPageModel {
                 chatroom_title,
                 chatroom_subtitle,
                 chatroom_stack,
+                members_button,
+                members_revealer,
+                profile_button,
+                group_info_button,
             },
         }
     }
@@ -190,111 +450,252 @@ impl Component for MainPageModel {
         &mut self,
         widgets: &mut Self::Widgets,
         msg: Self::Input,
-        _sender: &ComponentSender<Self>,
+        sender: &ComponentSender<Self>,
     ) {
         use MainMsg::*;
         match msg {
             WindowFolded => {
                 widgets.main_page.set_visible_child(&widgets.chatroom);
             }
-            SelectChatroom(account, is_group) => {
-                if !self.is_item_in_list(account, is_group) {
+            SelectChatroom(account, kind) => {
+                if !self.is_item_in_list(account, kind) {
                     // TODO: Get last_message from history or some other places
                     self.sidebar.sender().send(SidebarMsg::InsertChatItem(
                         account,
-                        is_group,
+                        kind,
                         String::new(),
                     ));
-                    self.insert_chatroom(account, is_group);
+                    self.insert_chatroom(account, kind);
                 }
 
-                let child_name =
-                    &format!("{} {}", account, if is_group { "group" } else { "friend" });
-                widgets.chatroom_stack.set_visible_child_name(child_name);
-
-                if is_group {
-                    let group_name: String = get_group_name(account);
-                    let title = group_name;
-                    let subtitle = account.to_string();
-                    widgets.chatroom_title.set_label(&title);
-                    widgets.chatroom_subtitle.set_label(&subtitle);
-                } else {
-                    let (user_name, user_remark): (String, String) = get_db()
-                        .query_row(
-                            "Select name, remark from friends where id=?1",
-                            [account],
-                            |row| Ok((row.get(0).unwrap(), row.get(1).unwrap())),
-                        )
-                        .unwrap();
-                    let title = &user_name;
-                    let subtitle = format!("{} ({})", user_remark, account);
-                    widgets.chatroom_title.set_label(title);
-                    widgets.chatroom_subtitle.set_label(&subtitle);
-                }
+                self.focus_chatroom(widgets, account, kind);
             }
             FriendMessage { friend_id, message } => {
-                use SidebarMsg::*;
-                if self.is_item_in_list(friend_id, false) {
-                    self.sidebar
-                        .sender()
-                        .send(UpdateChatItem(friend_id, false, message.text()));
-                } else {
-                    self.sidebar
-                        .sender()
-                        .send(InsertChatItem(friend_id, false, message.text()));
-                    self.insert_chatroom(friend_id, false);
-                    // 当所插入的 chatroom 为唯一的一个 chatroom 时，将其设为焦点，
-                    // 以触发自动更新 chatroom 的标题与副标题。
-                    if self.chatrooms.len() == 1 {
-                        let child_name = &format!("{} friend", friend_id);
-                        widgets.chatroom_stack.set_visible_child_name(child_name);
-
-                        let (user_name, user_remark): (String, String) = get_db()
-                            .query_row(
-                                "Select name, remark from friends where id=?1",
-                                [friend_id],
-                                |row| Ok((row.get(0).unwrap(), row.get(1).unwrap())),
-                            )
-                            .unwrap();
-                        let title = &user_name;
-                        let subtitle = format!("{} ({})", user_remark, friend_id);
-                        widgets.chatroom_title.set_label(title);
-                        widgets.chatroom_subtitle.set_label(&subtitle);
-                    }
-                }
-
-                self.push_friend_message(friend_id, message);
+                self.receive_message(widgets, friend_id, ChatKind::Friend, message);
             }
             GroupMessage { group_id, message } => {
-                use SidebarMsg::*;
-                if self.is_item_in_list(group_id, true) {
-                    self.sidebar
-                        .sender()
-                        .send(UpdateChatItem(group_id, true, message.text()));
-                } else {
-                    self.sidebar
-                        .sender()
-                        .send(InsertChatItem(group_id, true, message.text()));
-                    self.insert_chatroom(group_id, true);
-                    // 当所插入的 chatroom 为唯一的一个 chatroom 时，将其设为焦点，
-                    // 以触发自动更新 chatroom 的标题与副标题。
-                    if self.chatrooms.len() == 1 {
-                        let child_name = &format!("{} group", group_id);
-                        widgets.chatroom_stack.set_visible_child_name(child_name);
-
-                        let group_name: String = get_group_name(group_id);
-                        let title = group_name;
-                        let subtitle = group_id.to_string();
-                        widgets.chatroom_title.set_label(&title);
-                        widgets.chatroom_subtitle.set_label(&subtitle);
-                    }
+                self.receive_message(widgets, group_id, ChatKind::Group, message);
+            }
+            TempMessage {
+                group_id,
+                user_id,
+                message,
+            } => {
+                if message.sender_id == user_id {
+                    self.temp_names.insert(user_id, message.sender_name.clone());
+                }
+                self.receive_message(widgets, user_id, ChatKind::Temp { group_id }, message);
+            }
+            GroupEvent(event) => {
+                let name_of = |id| find_friend_remark(id).unwrap_or_else(|| id.to_string());
+                if let Some((group_id, notice)) = describe_group_event(&event, name_of) {
+                    self.push_group_notice(group_id, notice);
                 }
 
-                self.push_group_message(group_id, message);
+                match event {
+                    AppEvent::GroupNameUpdate { group_id, name, .. } => {
+                        let child_name = format!("{} group", group_id);
+                        if widgets.chatroom_stack.visible_child_name().as_deref()
+                            == Some(child_name.as_str())
+                        {
+                            widgets.chatroom_title.set_label(&name);
+                        }
+                        self.sidebar.sender().send(SidebarMsg::RenameChatItem(
+                            group_id,
+                            ChatKind::Group,
+                            name,
+                        ));
+                    }
+                    AppEvent::MemberCardChanged {
+                        group_id,
+                        member_id,
+                        name,
+                    } => {
+                        for i in 0..self.chatrooms.len() {
+                            let mut chatroom = self.chatrooms.get_mut(i);
+                            if chatroom.account == group_id && chatroom.kind == ChatKind::Group {
+                                chatroom.rename_sender(member_id, &name);
+                                break;
+                            }
+                        }
+                    }
+                    AppEvent::GroupDisband { group_id, .. } => {
+                        self.set_group_read_only(group_id);
+                    }
+                    AppEvent::GroupLeave {
+                        group_id,
+                        member_id,
+                        ..
+                    } if Some(&member_id) == ACCOUNT.get() => {
+                        self.set_group_read_only(group_id);
+                    }
+                    _ => {}
+                }
+            }
+            ForwardMessages(messages) => {
+                let window = Window::builder()
+                    .transient_for(&WINDOW.get().unwrap().window)
+                    .modal(true)
+                    .default_width(360)
+                    .default_height(520)
+                    .build();
+                // Forwarding to temp chats is not supported.
+                let recent_chats = (0..self.chatrooms.len())
+                    .filter_map(|i| {
+                        let chatroom = self.chatrooms.get(i);
+                        match chatroom.kind {
+                            ChatKind::Friend => Some((chatroom.account, false)),
+                            ChatKind::Group => Some((chatroom.account, true)),
+                            ChatKind::Temp { .. } => None,
+                        }
+                    })
+                    .collect();
+
+                let picker = ForwardPickerModel::builder()
+                    .launch(ForwardPickerPayload {
+                        window: window.clone(),
+                        messages,
+                        recent_chats,
+                    })
+                    .forward(sender.input_sender(), |message| message);
+
+                window.set_content(Some(picker.widget()));
+                window.present();
+            }
+            ShowCurrentProfile => {
+                if let Some(user_id) = self.current_user {
+                    sender.input(ShowProfile(user_id));
+                }
+            }
+            ShowProfile(user_id) => {
+                let window = Window::builder()
+                    .transient_for(&WINDOW.get().unwrap().window)
+                    .modal(true)
+                    .default_width(360)
+                    .default_height(560)
+                    .build();
+
+                let profile = ProfileModel::builder()
+                    .launch(ProfilePayload {
+                        window: window.clone(),
+                        user_id,
+                    })
+                    .forward(sender.input_sender(), |message| message);
+
+                window.set_content(Some(profile.widget()));
+                window.present();
+            }
+            ShowGroupInfo => {
+                let group_id = match self.current_group {
+                    Some(group_id) => group_id,
+                    None => return,
+                };
+                let window = Window::builder()
+                    .transient_for(&WINDOW.get().unwrap().window)
+                    .modal(true)
+                    .default_width(400)
+                    .default_height(640)
+                    .build();
+
+                let group_info = GroupInfoModel::builder()
+                    .launch(GroupInfoPayload {
+                        window: window.clone(),
+                        group_id,
+                    })
+                    .forward(sender.input_sender(), |message| message);
+
+                window.set_content(Some(group_info.widget()));
+                window.present();
+            }
+            // The main page exists, hidden, before logging in.
+            OpenSwitcher if ACCOUNT.get().is_none() => {}
+            OpenSwitcher => {
+                let window = Window::builder()
+                    .transient_for(&WINDOW.get().unwrap().window)
+                    .modal(true)
+                    .default_width(480)
+                    .default_height(420)
+                    .build();
+                let recent_chats = (0..self.chatrooms.len())
+                    .map(|i| {
+                        let chatroom = self.chatrooms.get(i);
+                        let (account, kind) = (chatroom.account, chatroom.kind);
+                        let name = match kind {
+                            ChatKind::Friend => {
+                                find_friend_remark(account).unwrap_or_else(|| account.to_string())
+                            }
+                            ChatKind::Group => get_group_name(account),
+                            ChatKind::Temp { group_id } => {
+                                self.temp_chat_title(account, group_id).0
+                            }
+                        };
+                        (account, kind, name)
+                    })
+                    .collect();
+
+                let switcher = SwitcherModel::builder()
+                    .launch(SwitcherPayload {
+                        window: window.clone(),
+                        recent_chats,
+                    })
+                    .forward(sender.input_sender(), |message| message);
+
+                window.set_content(Some(switcher.widget()));
+                window.present();
+            }
+            ShowAddFriend => {
+                let window = Window::builder()
+                    .transient_for(&WINDOW.get().unwrap().window)
+                    .modal(true)
+                    .default_width(360)
+                    .default_height(480)
+                    .build();
+
+                let add_friend = AddFriendModel::builder()
+                    .launch(AddFriendPayload {
+                        window: window.clone(),
+                    })
+                    .forward(sender.input_sender(), |message| message);
+
+                window.set_content(Some(add_friend.widget()));
+                window.present();
+            }
+            RenameFriend(friend_id, remark) => {
+                let child_name = chatroom_name(friend_id, ChatKind::Friend);
+                if widgets.chatroom_stack.visible_child_name().as_deref()
+                    == Some(child_name.as_str())
+                {
+                    widgets
+                        .chatroom_subtitle
+                        .set_label(&format!("{} ({})", remark, friend_id));
+                }
+                self.sidebar.sender().send(SidebarMsg::RenameChatItem(
+                    friend_id,
+                    ChatKind::Friend,
+                    remark,
+                ));
+            }
+            FriendAdded(friend_id, nickname) => {
+                sender.input(PushToast(format!(
+                    "{} ({}) is now your friend.",
+                    nickname, friend_id
+                )));
             }
             PushToast(content) => {
                 widgets.root.add_toast(&Toast::new(&content));
             }
+            ToggleMembers(active) => {
+                widgets.members_revealer.set_reveal_child(active);
+                if let (true, Some(group_id)) = (active, self.current_group) {
+                    self.members.emit(MembersMsg::Load(group_id));
+                }
+            }
+            ConnectionChanged(state) => {
+                let online = state == ConnectionState::Online;
+                for i in 0..self.chatrooms.len() {
+                    self.chatrooms.get_mut(i).set_online(online);
+                }
+            }
         }
     }
 }
@@ -0,0 +1,471 @@
+use relm4::{adw, gtk, ComponentParts, ComponentSender, SimpleComponent, WidgetPlus};
+
+use adw::{prelude::*, ActionRow, Avatar, HeaderBar, Window};
+use gtk::gdk_pixbuf::Pixbuf;
+use gtk::pango::EllipsizeMode;
+use gtk::{
+    Align, Box, Button, ButtonsType, Entry, Label, ListBox, MessageDialog, MessageType,
+    Orientation, Picture, ResponseType, ScrolledWindow, SelectionMode,
+};
+use ricq::structs::SummaryCardInfo;
+use tokio::task;
+
+use crate::db::friends;
+use crate::db::fs::{download_user_avatar_file, get_user_avatar_path};
+use crate::db::sql::{
+    get_common_groups, get_friend, get_groups, has_group_members, refresh_group_members, Friend,
+    Group,
+};
+use crate::handler::CLIENT;
+
+use super::{ChatKind, MainMsg};
+
+/// What the server tells about a user, beyond the local friends table.
+#[derive(Debug)]
+pub(crate) struct Summary {
+    pub(super) nickname: String,
+    pub(super) signature: String,
+    level: i32,
+    /// Zero when hidden.
+    age: u8,
+    sex: Option<&'static str>,
+    pub(super) city: String,
+}
+
+impl From<SummaryCardInfo> for Summary {
+    fn from(info: SummaryCardInfo) -> Self {
+        Summary {
+            nickname: info.nickname,
+            signature: info.sign,
+            level: info.level,
+            age: info.age,
+            sex: match info.sex {
+                0 => Some("Male"),
+                1 => Some("Female"),
+                _ => None,
+            },
+            city: info.city,
+        }
+    }
+}
+
+/// The profile of a user, who may be a friend or a stranger met in a group.
+/// Remarks and deletion are only offered for friends.
+pub(crate) struct ProfileModel {
+    window: Window,
+    user_id: i64,
+    friend: Option<Friend>,
+    summary: Option<Summary>,
+    common_groups: Vec<Group>,
+    is_busy: bool,
+}
+
+impl ProfileModel {
+    fn nickname(&self) -> String {
+        match (&self.summary, &self.friend) {
+            (Some(summary), _) => summary.nickname.clone(),
+            (None, Some(friend)) => friend.name.clone(),
+            (None, None) => self.user_id.to_string(),
+        }
+    }
+
+    fn summary_field<F: Fn(&Summary) -> Option<String>>(&self, field: F) -> Option<String> {
+        self.summary.as_ref().and_then(field)
+    }
+
+    fn confirm_delete(&self, sender: &ComponentSender<Self>) {
+        let dialog = MessageDialog::builder()
+            .transient_for(&self.window)
+            .modal(true)
+            .message_type(MessageType::Question)
+            .buttons(ButtonsType::None)
+            .text(&format!("Delete {}?", self.nickname()))
+            .secondary_text("The chat history will be kept.")
+            .build();
+        dialog.add_button("Cancel", ResponseType::Cancel);
+        dialog
+            .add_button("Delete", ResponseType::Accept)
+            .add_css_class("destructive-action");
+        let sender = sender.clone();
+        dialog.connect_response(move |dialog, response| {
+            if response == ResponseType::Accept {
+                sender.input(ProfileMsg::Delete);
+            }
+            dialog.close();
+        });
+        dialog.present();
+    }
+}
+
+pub(crate) struct Payload {
+    pub window: Window,
+    pub user_id: i64,
+}
+
+#[derive(Debug)]
+pub(crate) enum ProfileMsg {
+    Loaded(Summary),
+    CommonGroupsLoaded(Vec<Group>),
+    SendMessage,
+    SaveRemark(String),
+    RemarkSaved(String),
+    ConfirmDelete,
+    Delete,
+    Deleted,
+    Failed(String),
+}
+
+async fn load_summary(user_id: i64, sender: ComponentSender<ProfileModel>) {
+    let client = CLIENT.get().unwrap();
+    match client.get_summary_info(user_id).await {
+        Ok(info) => sender.input(ProfileMsg::Loaded(info.into())),
+        Err(err) => sender.input(ProfileMsg::Failed(format!(
+            "Failed to load the profile: {}",
+            err
+        ))),
+    }
+}
+
+/// The common groups are looked up in the cached group members, so the
+/// members of the groups which are not cached yet are fetched first.
+async fn load_common_groups(user_id: i64, sender: ComponentSender<ProfileModel>) {
+    let groups = get_groups().unwrap_or_else(|err| {
+        log::warn!("Failed to get the groups: {}", err);
+        Vec::new()
+    });
+    for group in groups {
+        if has_group_members(group.id) {
+            continue;
+        }
+        if let Err(err) = refresh_group_members(group.id).await {
+            log::warn!("Failed to refresh the members of {}: {}", group.id, err);
+        }
+    }
+
+    match get_common_groups(user_id) {
+        Ok(groups) => sender.input(ProfileMsg::CommonGroupsLoaded(groups)),
+        Err(err) => log::warn!("Failed to get the common groups of {}: {}", user_id, err),
+    }
+}
+
+async fn save_remark(user_id: i64, remark: String, sender: ComponentSender<ProfileModel>) {
+    match friends::edit_remark(user_id, remark.clone()).await {
+        Ok(()) => sender.input(ProfileMsg::RemarkSaved(remark)),
+        Err(err) => sender.input(ProfileMsg::Failed(format!(
+            "Failed to change the remark: {}",
+            err
+        ))),
+    }
+}
+
+async fn delete_friend(user_id: i64, sender: ComponentSender<ProfileModel>) {
+    match friends::delete_friend(user_id).await {
+        Ok(()) => sender.input(ProfileMsg::Deleted),
+        Err(err) => sender.input(ProfileMsg::Failed(format!(
+            "Failed to delete the friend: {}",
+            err
+        ))),
+    }
+}
+
+fn detail_row(title: &str) -> ActionRow {
+    relm4::view! {
+        row = ActionRow {
+            set_title: title,
+            set_visible: false,
+        }
+    }
+    row
+}
+
+fn show_detail(row: &ActionRow, value: Option<String>) {
+    row.set_visible(value.is_some());
+    row.set_subtitle(&value.unwrap_or_default());
+}
+
+#[derive(Debug)]
+pub(crate) struct ProfileWidgets {
+    nickname_label: Label,
+    signature_label: Label,
+    level_row: ActionRow,
+    age_row: ActionRow,
+    sex_row: ActionRow,
+    location_row: ActionRow,
+    remark_entry: Entry,
+    delete_button: Button,
+    common_groups_label: Label,
+    groups_list: ListBox,
+    /// The ids of the groups in `groups_list`.
+    shown_groups: Vec<i64>,
+}
+
+impl SimpleComponent for ProfileModel {
+    type Input = ProfileMsg;
+    type Output = MainMsg;
+    type InitParams = Payload;
+    type Widgets = ProfileWidgets;
+    type Root = Box;
+
+    fn init_root() -> Self::Root {
+        Box::new(Orientation::Vertical, 0)
+    }
+
+    fn init(
+        params: Self::InitParams,
+        root: &Self::Root,
+        sender: &ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let user_id = params.user_id;
+        let model = ProfileModel {
+            window: params.window,
+            user_id,
+            friend: get_friend(user_id),
+            summary: None,
+            common_groups: get_common_groups(user_id).unwrap_or_else(|err| {
+                log::warn!("Failed to get the common groups of {}: {}", user_id, err);
+                Vec::new()
+            }),
+            is_busy: false,
+        };
+        let is_friend = model.friend.is_some();
+        let remark = model
+            .friend
+            .as_ref()
+            .map(|friend| friend.remark.clone())
+            .unwrap_or_default();
+
+        let level_row = detail_row("Level");
+        let age_row = detail_row("Age");
+        let sex_row = detail_row("Sex");
+        let location_row = detail_row("Location");
+
+        relm4::view! {
+            header_bar = HeaderBar {
+                set_title_widget = Some(&Label) {
+                    set_label: "Profile",
+                },
+            }
+        }
+
+        relm4::view! {
+            scrolled_window = ScrolledWindow {
+                set_vexpand: true,
+                set_child = Some(&Box) {
+                    set_orientation: Orientation::Vertical,
+                    set_spacing: 12,
+                    set_margin_all: 24,
+                    #[name = "avatar"]
+                    Avatar {
+                        set_size: 96,
+                        set_text: Some(&model.nickname()),
+                        set_show_initials: true,
+                        set_halign: Align::Center,
+                    },
+                    #[name = "nickname_label"]
+                    Label {
+                        set_label: &model.nickname(),
+                        set_selectable: true,
+                        set_ellipsize: EllipsizeMode::End,
+                        add_css_class: "title-2",
+                    },
+                    Label {
+                        set_label: &user_id.to_string(),
+                        set_selectable: true,
+                        set_css_classes: &["caption", "dim-label"],
+                    },
+                    #[name = "signature_label"]
+                    Label {
+                        set_wrap: true,
+                        set_justify: gtk::Justification::Center,
+                        set_visible: false,
+                    },
+                    Box {
+                        set_halign: Align::Center,
+                        set_spacing: 8,
+                        Button {
+                            set_label: "Message",
+                            set_visible: is_friend,
+                            add_css_class: "suggested-action",
+                            connect_clicked[sender] => move |_| {
+                                sender.input(ProfileMsg::SendMessage);
+                            },
+                        },
+                        #[name = "delete_button"]
+                        Button {
+                            set_label: "Delete Friend",
+                            set_visible: is_friend,
+                            add_css_class: "destructive-action",
+                            connect_clicked[sender] => move |_| {
+                                sender.input(ProfileMsg::ConfirmDelete);
+                            },
+                        },
+                    },
+                    ListBox {
+                        set_selection_mode: SelectionMode::None,
+                        add_css_class: "boxed-list",
+                        append = &ActionRow {
+                            set_title: "Remark",
+                            set_visible: is_friend,
+                            add_suffix: remark_entry = &Entry {
+                                set_valign: Align::Center,
+                                set_text: &remark,
+                                set_placeholder_text: Some("No remark"),
+                                connect_activate[sender] => move |entry| {
+                                    sender.input(ProfileMsg::SaveRemark(entry.buffer().text()));
+                                },
+                            },
+                        },
+                        append: &level_row,
+                        append: &age_row,
+                        append: &sex_row,
+                        append: &location_row,
+                    },
+                    #[name = "common_groups_label"]
+                    Label {
+                        set_label: "Common Groups",
+                        set_halign: Align::Start,
+                        set_visible: false,
+                        add_css_class: "heading",
+                    },
+                    #[name = "groups_list"]
+                    ListBox {
+                        set_selection_mode: SelectionMode::None,
+                        set_visible: false,
+                        add_css_class: "boxed-list",
+                    },
+                }
+            }
+        }
+
+        let avatar_path = get_user_avatar_path(user_id);
+        if avatar_path.exists() {
+            if let Ok(pixbuf) = Pixbuf::from_file_at_size(avatar_path, 96, 96) {
+                let image = Picture::for_pixbuf(&pixbuf);
+                if let Some(paintable) = image.paintable() {
+                    avatar.set_custom_image(Some(&paintable));
+                }
+            }
+        } else {
+            task::spawn(download_user_avatar_file(user_id));
+        }
+
+        root.append(&header_bar);
+        root.append(&scrolled_window);
+
+        task::spawn(load_summary(user_id, sender.clone()));
+        task::spawn(load_common_groups(user_id, sender.clone()));
+
+        let mut widgets = ProfileWidgets {
+            nickname_label,
+            signature_label,
+            level_row,
+            age_row,
+            sex_row,
+            location_row,
+            remark_entry,
+            delete_button,
+            common_groups_label,
+            groups_list,
+            shown_groups: Vec::new(),
+        };
+        // Show the cached common groups until they are loaded.
+        model.update_view(&mut widgets, sender);
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: ProfileMsg, sender: &ComponentSender<Self>) {
+        use ProfileMsg::*;
+        match msg {
+            Loaded(summary) => self.summary = Some(summary),
+            CommonGroupsLoaded(groups) => self.common_groups = groups,
+            SendMessage => {
+                sender.output(MainMsg::SelectChatroom(self.user_id, ChatKind::Friend));
+                self.window.close();
+            }
+            SaveRemark(remark) => {
+                let unchanged = self.friend.as_ref().map(|friend| &friend.remark) == Some(&remark);
+                if !unchanged && !self.is_busy {
+                    self.is_busy = true;
+                    task::spawn(save_remark(self.user_id, remark, sender.clone()));
+                }
+            }
+            RemarkSaved(remark) => {
+                self.is_busy = false;
+                if let Some(friend) = self.friend.as_mut() {
+                    friend.remark = remark;
+                }
+            }
+            ConfirmDelete => self.confirm_delete(sender),
+            Delete => {
+                if !self.is_busy {
+                    self.is_busy = true;
+                    task::spawn(delete_friend(self.user_id, sender.clone()));
+                }
+            }
+            Deleted => {
+                sender.output(MainMsg::PushToast(format!(
+                    "Deleted {} from your friends.",
+                    self.nickname()
+                )));
+                self.window.close();
+            }
+            Failed(err) => {
+                self.is_busy = false;
+                sender.output(MainMsg::PushToast(err));
+            }
+        }
+    }
+
+    fn update_view(&self, widgets: &mut Self::Widgets, _sender: &ComponentSender<Self>) {
+        widgets.nickname_label.set_label(&self.nickname());
+
+        let group_ids: Vec<i64> = self.common_groups.iter().map(|group| group.id).collect();
+        if widgets.shown_groups != group_ids {
+            while let Some(row) = widgets.groups_list.first_child() {
+                widgets.groups_list.remove(&row);
+            }
+            for group in self.common_groups.iter() {
+                relm4::view! {
+                    row = ActionRow {
+                        set_title: &group.name,
+                        set_subtitle: &group.id.to_string(),
+                    }
+                }
+                widgets.groups_list.append(&row);
+            }
+            widgets
+                .common_groups_label
+                .set_visible(!group_ids.is_empty());
+            widgets.groups_list.set_visible(!group_ids.is_empty());
+            widgets.shown_groups = group_ids;
+        }
+        widgets.remark_entry.set_sensitive(!self.is_busy);
+        widgets.delete_button.set_sensitive(!self.is_busy);
+
+        let signature = self.summary_field(|summary| {
+            (!summary.signature.is_empty()).then(|| summary.signature.clone())
+        });
+        widgets.signature_label.set_visible(signature.is_some());
+        widgets
+            .signature_label
+            .set_label(&signature.unwrap_or_default());
+
+        show_detail(
+            &widgets.level_row,
+            self.summary_field(|summary| Some(summary.level.to_string())),
+        );
+        show_detail(
+            &widgets.age_row,
+            self.summary_field(|summary| (summary.age > 0).then(|| summary.age.to_string())),
+        );
+        show_detail(
+            &widgets.sex_row,
+            self.summary_field(|summary| summary.sex.map(str::to_string)),
+        );
+        show_detail(
+            &widgets.location_row,
+            self.summary_field(|summary| (!summary.city.is_empty()).then(|| summary.city.clone())),
+        );
+    }
+}
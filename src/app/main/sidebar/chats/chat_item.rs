@@ -13,25 +13,27 @@ use crate::db::{
         download_group_avatar_file, download_user_avatar_file, get_group_avatar_path,
         get_user_avatar_path,
     },
-    sql::{get_friend_remark, get_group_name},
+    sql::{find_friend_remark, get_friend_remark, get_group_name},
 };
 
 use super::ChatsMsg;
+use crate::app::main::ChatKind;
 
 #[derive(Debug)]
 pub struct ChatItem {
     pub account: i64,
     pub name: String,
-    pub is_group: bool,
+    pub kind: ChatKind,
     pub last_message: String,
 }
 
 pub struct ChatItemWidgets {
+    pub name: Label,
     pub last_message: Label,
 }
 
 impl FactoryComponent<ListBox, ChatsMsg> for ChatItem {
-    type InitParams = (i64, bool, String);
+    type InitParams = (i64, ChatKind, String);
     type Widgets = ChatItemWidgets;
     type Input = ();
     type Output = ();
@@ -68,7 +70,7 @@ impl FactoryComponent<ListBox, ChatsMsg> for ChatItem {
             }
         };
 
-        if self.is_group {
+        if self.kind.is_group() {
             let avatar_path = get_group_avatar_path(self.account);
             if avatar_path.exists() {
                 if let Ok(pixbuf) = Pixbuf::from_file_at_size(avatar_path, 48, 48) {
@@ -100,12 +102,20 @@ impl FactoryComponent<ListBox, ChatsMsg> for ChatItem {
                 set_orientation: Orientation::Vertical,
                 set_halign: Align::Start,
                 set_spacing: 8,
+                #[name = "name"]
                 Label {
                     set_xalign: 0.0,
                     set_text: self.name.as_str(),
                     set_ellipsize: EllipsizeMode::End,
                     add_css_class: "heading"
                 },
+                #[name = "via"]
+                Label {
+                    set_xalign: 0.0,
+                    set_visible: false,
+                    set_ellipsize: EllipsizeMode::End,
+                    set_css_classes: &["caption", "dim-label"],
+                },
                 #[name = "last_message"]
                 Label {
                     set_text: self.last_message.as_str(),
@@ -116,10 +126,15 @@ impl FactoryComponent<ListBox, ChatsMsg> for ChatItem {
             }
         };
 
+        if let ChatKind::Temp { group_id } = self.kind {
+            via.set_label(&format!("via {}", get_group_name(group_id)));
+            via.set_visible(true);
+        }
+
         root.append(&avatar);
         root.append(&info);
 
-        ChatItemWidgets { last_message }
+        ChatItemWidgets { name, last_message }
     }
 
     fn init_model(
@@ -128,16 +143,19 @@ impl FactoryComponent<ListBox, ChatsMsg> for ChatItem {
         _input: &Sender<Self::Input>,
         _output: &Sender<Self::Output>,
     ) -> Self {
-        let (account, is_group, last_message) = init_params;
+        let (account, kind, last_message) = init_params;
         let last_message = last_message.replace('\n', " ");
-        let name = if is_group {
-            get_group_name(account)
-        } else {
-            get_friend_remark(account)
+        let name = match kind {
+            ChatKind::Friend => get_friend_remark(account),
+            ChatKind::Group => get_group_name(account),
+            // Renamed to the nickname of the stranger once it is known.
+            ChatKind::Temp { .. } => {
+                find_friend_remark(account).unwrap_or_else(|| account.to_string())
+            }
         };
         ChatItem {
             account,
-            is_group,
+            kind,
             name,
             last_message,
         }
@@ -149,6 +167,7 @@ impl FactoryComponent<ListBox, ChatsMsg> for ChatItem {
         _input: &Sender<Self::Input>,
         _output: &Sender<Self::Output>,
     ) {
+        widgets.name.set_label(&self.name);
         widgets.last_message.set_label(&self.last_message);
     }
 }
@@ -7,6 +7,7 @@ use adw::prelude::*;
 use gtk::{ListBox, ScrolledWindow};
 
 use super::SidebarMsg;
+use crate::app::main::ChatKind;
 use chat_item::ChatItem;
 
 #[derive(Debug)]
@@ -15,11 +16,11 @@ pub struct ChatsModel {
 }
 
 impl ChatsModel {
-    fn update_chat_item(&mut self, account: i64, is_group: bool, last_message: String) {
+    fn update_chat_item(&mut self, account: i64, kind: ChatKind, last_message: String) {
         for i in 0..self.chats_list.len() {
             let this_account = self.chats_list.get(i).account;
-            let is_this_group = self.chats_list.get(i).is_group;
-            if this_account == account && is_this_group == is_group {
+            let this_kind = self.chats_list.get(i).kind;
+            if this_account == account && this_kind == kind {
                 self.chats_list.swap(0, i);
                 self.chats_list.front_mut().unwrap().last_message = last_message;
                 break;
@@ -28,9 +29,19 @@ impl ChatsModel {
         self.chats_list.render_changes();
     }
 
-    fn insert_chat_item(&mut self, account: i64, is_group: bool, last_message: String) {
-        self.chats_list
-            .push_front((account, is_group, last_message));
+    fn rename_chat_item(&mut self, account: i64, kind: ChatKind, name: String) {
+        for i in 0..self.chats_list.len() {
+            let mut chat_item = self.chats_list.get_mut(i);
+            if chat_item.account == account && chat_item.kind == kind {
+                chat_item.name = name;
+                break;
+            }
+        }
+        self.chats_list.render_changes();
+    }
+
+    fn insert_chat_item(&mut self, account: i64, kind: ChatKind, last_message: String) {
+        self.chats_list.push_front((account, kind, last_message));
         self.chats_list.render_changes();
     }
 }
@@ -38,8 +49,9 @@ impl ChatsModel {
 #[derive(Debug)]
 pub enum ChatsMsg {
     SelectChatroom(i32),
-    UpdateChatItem(i64, bool, String),
-    InsertChatItem(i64, bool, String),
+    UpdateChatItem(i64, ChatKind, String),
+    InsertChatItem(i64, ChatKind, String),
+    RenameChatItem(i64, ChatKind, String),
 }
 
 #[relm4::component(pub)]
@@ -83,15 +95,16 @@ impl SimpleComponent for ChatsModel {
             SelectChatroom(index) => {
                 let chat_item = self.chats_list.get(index as usize);
                 let account = chat_item.account;
-                let is_group = chat_item.is_group;
-                sender.output(SidebarMsg::SelectChatroom(account, is_group));
+                let kind = chat_item.kind;
+                sender.output(SidebarMsg::SelectChatroom(account, kind));
             }
-            UpdateChatItem(account, is_group, last_message) => {
-                self.update_chat_item(account, is_group, last_message)
+            UpdateChatItem(account, kind, last_message) => {
+                self.update_chat_item(account, kind, last_message)
             }
-            InsertChatItem(account, is_group, last_message) => {
-                self.insert_chat_item(account, is_group, last_message)
+            InsertChatItem(account, kind, last_message) => {
+                self.insert_chat_item(account, kind, last_message)
             }
+            RenameChatItem(account, kind, name) => self.rename_chat_item(account, kind, name),
         }
     }
 }
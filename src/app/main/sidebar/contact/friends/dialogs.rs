@@ -0,0 +1,75 @@
+use relm4::gtk;
+
+use gtk::prelude::*;
+use gtk::{Box, ButtonsType, DropDown, Entry, MessageDialog, MessageType, ResponseType};
+
+use crate::global::WINDOW;
+
+fn dialog(title: &str, body: &str, action: &str) -> MessageDialog {
+    let dialog = MessageDialog::builder()
+        .transient_for(&WINDOW.get().unwrap().window)
+        .modal(true)
+        .message_type(MessageType::Question)
+        .buttons(ButtonsType::None)
+        .text(title)
+        .secondary_text(body)
+        .build();
+    dialog.add_button("Cancel", ResponseType::Cancel);
+    dialog.add_button(action, ResponseType::Accept);
+    dialog.set_default_response(ResponseType::Accept);
+    dialog
+}
+
+fn message_area(dialog: &MessageDialog) -> Box {
+    dialog.message_area().downcast().unwrap()
+}
+
+/// Ask before doing something which cannot be undone.
+pub(super) fn confirm<F: Fn() + 'static>(title: &str, body: &str, action: &str, on_confirm: F) {
+    let dialog = dialog(title, body, action);
+    if let Some(button) = dialog.widget_for_response(ResponseType::Accept) {
+        button.add_css_class("destructive-action");
+    }
+    dialog.connect_response(move |dialog, response| {
+        if response == ResponseType::Accept {
+            on_confirm();
+        }
+        dialog.close();
+    });
+    dialog.present();
+}
+
+/// Ask for a line of text, starting from `text`.
+pub(super) fn prompt<F: Fn(String) + 'static>(title: &str, text: &str, on_submit: F) {
+    let dialog = dialog(title, "", "Save");
+    let entry = Entry::builder().text(text).activates_default(true).build();
+    message_area(&dialog).append(&entry);
+    dialog.connect_response(move |dialog, response| {
+        if response == ResponseType::Accept {
+            on_submit(entry.buffer().text());
+        }
+        dialog.close();
+    });
+    dialog.present();
+}
+
+/// Ask to pick one of `options`, starting from the `selected` one.
+pub(super) fn choose<F: Fn(u32) + 'static>(
+    title: &str,
+    action: &str,
+    options: &[&str],
+    selected: u32,
+    on_choose: F,
+) {
+    let dialog = dialog(title, "", action);
+    let drop_down = DropDown::from_strings(options);
+    drop_down.set_selected(selected);
+    message_area(&dialog).append(&drop_down);
+    dialog.connect_response(move |dialog, response| {
+        if response == ResponseType::Accept {
+            on_choose(drop_down.selected());
+        }
+        dialog.close();
+    });
+    dialog.present();
+}
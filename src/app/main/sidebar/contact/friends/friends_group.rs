@@ -1,11 +1,16 @@
+use relm4::actions::{RelmAction, RelmActionGroup};
 use relm4::factory::{DynamicIndex, FactoryComponent};
 use relm4::{adw, gtk, Sender, WidgetPlus};
 
 use adw::{prelude::*, Avatar, ExpanderRow};
+use gtk::gdk::Rectangle;
 use gtk::gdk_pixbuf::Pixbuf;
 use gtk::glib::clone;
 use gtk::pango::EllipsizeMode;
-use gtk::{Align, Box, GestureClick, Label, Orientation, Picture, Widget};
+use gtk::{
+    Align, Box, Button, GestureClick, Label, MenuButton, Orientation, Picture, Popover,
+    PopoverMenu, Widget,
+};
 
 use tokio::task;
 
@@ -15,6 +20,61 @@ use crate::db::sql::Friend;
 
 pub enum FriendsGroupMessage {
     SelectUser(i64),
+    ShowProfile(i64),
+    EditRemark(i64),
+    MoveFriend(i64),
+    DeleteFriend(i64),
+    Rename,
+    Delete,
+}
+
+relm4::new_action_group!(FriendActionGroup, "friend");
+relm4::new_stateless_action!(EditRemarkAction, FriendActionGroup, "edit-remark");
+relm4::new_stateless_action!(MoveFriendAction, FriendActionGroup, "move");
+relm4::new_stateless_action!(DeleteFriendAction, FriendActionGroup, "delete");
+
+/// Show a context menu with the management actions when the row of
+/// `friend_id` is right clicked.
+fn attach_context_menu(row: &Box, friend_id: i64, input: &Sender<FriendsGroupMessage>) {
+    let edit_remark: RelmAction<EditRemarkAction> =
+        RelmAction::new_stateless(clone!(@strong input => move |_| {
+            input.send(FriendsGroupMessage::EditRemark(friend_id));
+        }));
+    let move_friend: RelmAction<MoveFriendAction> =
+        RelmAction::new_stateless(clone!(@strong input => move |_| {
+            input.send(FriendsGroupMessage::MoveFriend(friend_id));
+        }));
+    let delete_friend: RelmAction<DeleteFriendAction> =
+        RelmAction::new_stateless(clone!(@strong input => move |_| {
+            input.send(FriendsGroupMessage::DeleteFriend(friend_id));
+        }));
+    let actions: RelmActionGroup<FriendActionGroup> = RelmActionGroup::new();
+    actions.add_action(edit_remark);
+    actions.add_action(move_friend);
+    actions.add_action(delete_friend);
+    row.insert_action_group("friend", Some(&actions.into_action_group()));
+
+    relm4::menu! {
+        friend_menu: {
+            "Edit Remark…" => EditRemarkAction,
+            "Move to…" => MoveFriendAction,
+            "Delete Friend…" => DeleteFriendAction
+        }
+    }
+
+    let popover = PopoverMenu::from_model(Some(&friend_menu));
+    popover.set_has_arrow(false);
+    popover.set_parent(row);
+    // The row does not unparent the popover by itself, as it is not a child
+    // added by the row.
+    row.connect_destroy(clone!(@weak popover => move |_| popover.unparent()));
+
+    let gesture = GestureClick::builder().button(3).build();
+    gesture.connect_pressed(clone!(@weak popover => move |_, _, x, y| {
+        popover.set_pointing_to(Some(&Rectangle::new(x as i32, y as i32, 1, 1)));
+        popover.popup();
+    }));
+    row.add_controller(&gesture);
 }
 
 #[derive(Debug, Clone)]
@@ -66,6 +126,40 @@ impl FactoryComponent<Box, FriendsMsg> for FriendsGroup {
         input: &Sender<Self::Input>,
         _output: &Sender<Self::Output>,
     ) -> Self::Widgets {
+        // The default group cannot be deleted.
+        let is_default = self.id == 0;
+        relm4::view! {
+            menu_button = MenuButton {
+                set_icon_name: "view-more-symbolic",
+                set_tooltip_text: Some("Manage the group"),
+                set_valign: Align::Center,
+                add_css_class: "flat",
+                set_popover: popover = Some(&Popover) {
+                    set_child = Some(&Box) {
+                        set_orientation: Orientation::Vertical,
+                        Button {
+                            set_label: "Rename…",
+                            add_css_class: "flat",
+                            connect_clicked[popover, input] => move |_| {
+                                popover.popdown();
+                                input.send(FriendsGroupMessage::Rename);
+                            },
+                        },
+                        Button {
+                            set_label: "Delete…",
+                            set_visible: !is_default,
+                            add_css_class: "flat",
+                            connect_clicked[popover, input] => move |_| {
+                                popover.popdown();
+                                input.send(FriendsGroupMessage::Delete);
+                            },
+                        },
+                    }
+                },
+            }
+        }
+        group.add_action(&menu_button);
+
         let friends = self.friends.clone();
         for friend in friends.into_iter() {
             // Create user item click event
@@ -101,6 +195,17 @@ impl FactoryComponent<Box, FriendsMsg> for FriendsGroup {
                             set_ellipsize: EllipsizeMode::End,
                         },
                     },
+                    Button {
+                        set_icon_name: "avatar-default-symbolic",
+                        set_tooltip_text: Some("Profile"),
+                        set_valign: Align::Center,
+                        set_hexpand: true,
+                        set_halign: Align::End,
+                        add_css_class: "flat",
+                        connect_clicked[input] => move |_| {
+                            input.send(FriendsGroupMessage::ShowProfile(friend.id));
+                        },
+                    },
                     add_controller: &gesture,
                 }
             }
@@ -117,6 +222,7 @@ impl FactoryComponent<Box, FriendsMsg> for FriendsGroup {
                 task::spawn(download_user_avatar_file(friend.id));
             }
 
+            attach_context_menu(&child, friend.id, input);
             group.add_row(&child);
         }
     }
@@ -132,6 +238,12 @@ impl FactoryComponent<Box, FriendsMsg> for FriendsGroup {
             SelectUser(account) => {
                 output.send(FriendsMsg::SelectChatroom(account, false));
             }
+            ShowProfile(account) => output.send(FriendsMsg::ShowProfile(account)),
+            EditRemark(account) => output.send(FriendsMsg::EditRemark(account)),
+            MoveFriend(account) => output.send(FriendsMsg::MoveFriend(account)),
+            DeleteFriend(account) => output.send(FriendsMsg::DeleteFriend(account)),
+            Rename => output.send(FriendsMsg::RenameCategory(self.id)),
+            Delete => output.send(FriendsMsg::DeleteCategory(self.id)),
         }
         None
     }
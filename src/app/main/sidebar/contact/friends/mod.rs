@@ -1,6 +1,10 @@
+mod dialogs;
 mod friends_group;
 mod search_item;
 
+use std::error::Error;
+use std::future::Future;
+
 use tokio::task;
 
 use relm4::factory::FactoryVecDeque;
@@ -10,13 +14,20 @@ use adw::prelude::*;
 use gtk::{Box, Button, Entry, EntryIconPosition, ListBox, Orientation, ScrolledWindow};
 
 use super::ContactMsg;
-use crate::db::sql::{get_db, refresh_friends_list, Friend};
+use crate::db::friends;
+use crate::db::sql::{
+    find_friend_remark, get_db, get_friend, get_friends_groups, refresh_friends_list, Friend,
+};
+use crate::event::{self, AppEvent};
+use crate::utils::search::SearchIndex;
 use friends_group::FriendsGroup;
+use search_item::FriendSearchItem;
 
 #[derive(Debug)]
 pub struct FriendsModel {
     friends_list: Option<FactoryVecDeque<Box, FriendsGroup, FriendsMsg>>,
-    search_list: Option<FactoryVecDeque<ListBox, Friend, FriendsMsg>>,
+    search_list: Option<FactoryVecDeque<ListBox, FriendSearchItem, FriendsMsg>>,
+    search_index: SearchIndex<Friend>,
     is_refresh_button_enabled: bool,
 }
 
@@ -40,6 +51,12 @@ impl FriendsModel {
             .map(|result| result.unwrap())
             .collect();
 
+        self.search_index = SearchIndex::default();
+        for friend in friends.iter() {
+            self.search_index
+                .insert(friend.clone(), friend.id, &[&friend.remark, &friend.name]);
+        }
+
         let friends_groups: Vec<FriendsGroup> = conn
             .prepare("Select id, name, online_friends from friends_groups")?
             .query_map([], |row| {
@@ -66,39 +83,15 @@ impl FriendsModel {
         Ok(())
     }
 
-    fn render_search_result(&mut self, keyword: String) -> rusqlite::Result<()> {
+    fn render_search_result(&mut self, keyword: String) {
         let search_list = self.search_list.as_mut().unwrap();
         search_list.clear();
 
-        let keyword = keyword.to_lowercase();
-        let conn = get_db();
-
-        let mut stmt = conn.prepare("Select id, name, remark, group_id from friends")?;
-        let eligible_friends: Vec<Friend> = stmt
-            .query_map([], |row| {
-                Ok(Friend {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    remark: row.get(2)?,
-                    group_id: row.get(3)?,
-                })
-            })?
-            .map(|result| result.unwrap())
-            .filter(|friend| {
-                let match_name = friend.name.to_lowercase().contains(&keyword);
-                let match_remark = friend.remark.to_lowercase().contains(&keyword);
-
-                match_name || match_remark
-            })
-            .collect();
-
-        for friend in eligible_friends {
-            search_list.push_back(friend);
+        for hit in self.search_index.search(&keyword) {
+            search_list.push_back(hit.into());
         }
 
         search_list.render_changes();
-
-        Ok(())
     }
 }
 
@@ -112,13 +105,47 @@ async fn refresh_friends(sender: ComponentSender<FriendsModel>) {
     }
 }
 
+/// Run a friend management operation, and report how it went.
+fn manage<F>(sender: &ComponentSender<FriendsModel>, operation: F, done: String)
+where
+    F: Future<Output = Result<(), std::boxed::Box<dyn Error + Send + Sync>>> + Send + 'static,
+{
+    let sender = sender.clone();
+    task::spawn(async move {
+        match operation.await {
+            Ok(()) => sender.output(ContactMsg::PushToast(done)),
+            Err(err) => sender.output(ContactMsg::PushToast(err.to_string())),
+        }
+    });
+}
+
+fn category_name(category_id: u8) -> String {
+    get_db()
+        .query_row(
+            "Select name from friends_groups where id=?1",
+            [category_id],
+            |row| row.get(0),
+        )
+        .unwrap_or_default()
+}
+
 #[derive(Debug)]
 pub enum FriendsMsg {
     SelectChatroom(i64, bool),
+    ShowProfile(i64),
+    AddFriend,
     SelectSearchItem(i32),
     Search(String),
     Refresh,
     Render,
+    /// Render again without a toast, after a sync in the background.
+    Reload,
+    EditRemark(i64),
+    MoveFriend(i64),
+    DeleteFriend(i64),
+    CreateCategory,
+    RenameCategory(u8),
+    DeleteCategory(u8),
 }
 
 #[derive(Debug)]
@@ -148,6 +175,7 @@ impl Component for FriendsModel {
         let mut model = FriendsModel {
             friends_list: None,
             search_list: None,
+            search_index: SearchIndex::default(),
             is_refresh_button_enabled: true,
         };
 
@@ -165,11 +193,27 @@ impl Component for FriendsModel {
                         sender.input(FriendsMsg::Refresh);
                     },
                 },
+                Button {
+                    set_tooltip_text: Some("Add friend"),
+                    set_icon_name: "contact-new-symbolic",
+                    set_margin_end: 8,
+                    connect_clicked[sender] => move |_| {
+                        sender.input(FriendsMsg::AddFriend);
+                    },
+                },
+                Button {
+                    set_tooltip_text: Some("New friend group"),
+                    set_icon_name: "list-add-symbolic",
+                    set_margin_end: 8,
+                    connect_clicked[sender] => move |_| {
+                        sender.input(FriendsMsg::CreateCategory);
+                    },
+                },
                 #[name = "search_entry"]
                 Entry {
                     set_icon_from_icon_name: (EntryIconPosition::Secondary, Some("system-search-symbolic")),
                     set_placeholder_text: Some("Search in friends..."),
-                    set_width_request: 320 - 3 * 8 - 32,
+                    set_width_request: 320 - 5 * 8 - 3 * 32,
                     connect_changed[sender] => move |entry| {
                         let keywords = entry.buffer().text();
                         sender.input(FriendsMsg::Search(keywords));
@@ -199,7 +243,7 @@ impl Component for FriendsModel {
 
         let friend_list_factory: FactoryVecDeque<Box, FriendsGroup, FriendsMsg> =
             FactoryVecDeque::new(friend_list.clone(), &sender.input);
-        let search_list_factory: FactoryVecDeque<ListBox, Friend, FriendsMsg> =
+        let search_list_factory: FactoryVecDeque<ListBox, FriendSearchItem, FriendsMsg> =
             FactoryVecDeque::new(search_list.clone(), &sender.input);
 
         model.friends_list = Some(friend_list_factory);
@@ -207,6 +251,11 @@ impl Component for FriendsModel {
 
         model.render_friends().unwrap();
 
+        event::forward(sender.input.clone(), |event| match event {
+            AppEvent::ContactsUpdated { friends: true, .. } => Some(FriendsMsg::Reload),
+            _ => None,
+        });
+
         ComponentParts {
             model,
             widgets: FriendsWidgets {
@@ -228,8 +277,16 @@ impl Component for FriendsModel {
             SelectChatroom(account, is_group) => {
                 sender.output(ContactMsg::SelectChatroom(account, is_group));
             }
+            ShowProfile(account) => sender.output(ContactMsg::ShowProfile(account)),
+            AddFriend => sender.output(ContactMsg::AddFriend),
             SelectSearchItem(index) => {
-                let account = self.search_list.as_ref().unwrap().get(index as usize).id;
+                let account = self
+                    .search_list
+                    .as_ref()
+                    .unwrap()
+                    .get(index as usize)
+                    .friend
+                    .id;
                 sender.input(SelectChatroom(account, false));
             }
             Refresh => {
@@ -245,15 +302,98 @@ impl Component for FriendsModel {
                 }
                 self.is_refresh_button_enabled = true;
             }
+            Reload => {
+                if let Err(err) = self.render_friends() {
+                    log::warn!("Failed to render the friends list: {}", err);
+                }
+            }
+            EditRemark(account) => {
+                let remark = find_friend_remark(account).unwrap_or_default();
+                let sender = sender.clone();
+                dialogs::prompt("Edit Remark", &remark, move |remark| {
+                    let done = "Changed the remark.".to_string();
+                    manage(&sender, friends::edit_remark(account, remark), done);
+                });
+            }
+            MoveFriend(account) => {
+                let categories = match get_friends_groups() {
+                    Ok(categories) => categories,
+                    Err(err) => return sender.output(ContactMsg::PushToast(err.to_string())),
+                };
+                let current = get_friend(account).map(|friend| friend.group_id);
+                let selected = categories
+                    .iter()
+                    .position(|category| Some(category.id) == current)
+                    .unwrap_or_default();
+                let names: Vec<&str> = categories
+                    .iter()
+                    .map(|category| category.name.as_str())
+                    .collect();
+                let ids: Vec<u8> = categories.iter().map(|category| category.id).collect();
+                let sender = sender.clone();
+                dialogs::choose("Move to", "Move", &names, selected as u32, move |index| {
+                    let category_id = ids[index as usize];
+                    if Some(category_id) != current {
+                        let done = "Moved the friend.".to_string();
+                        manage(&sender, friends::move_friend(account, category_id), done);
+                    }
+                });
+            }
+            DeleteFriend(account) => {
+                let name = find_friend_remark(account).unwrap_or_else(|| account.to_string());
+                let sender = sender.clone();
+                dialogs::confirm(
+                    &format!("Delete {}?", name),
+                    "The chat history will be kept.",
+                    "Delete",
+                    move || {
+                        let done = format!("Deleted {} from your friends.", name);
+                        manage(&sender, friends::delete_friend(account), done);
+                    },
+                );
+            }
+            CreateCategory => {
+                let sender = sender.clone();
+                dialogs::prompt("New Friend Group", "", move |name| {
+                    if !name.is_empty() {
+                        let done = format!("Created {}.", name);
+                        manage(&sender, friends::create_category(name), done);
+                    }
+                });
+            }
+            RenameCategory(category_id) => {
+                let sender = sender.clone();
+                dialogs::prompt(
+                    "Rename Friend Group",
+                    &category_name(category_id),
+                    move |name| {
+                        if !name.is_empty() {
+                            let done = format!("Renamed to {}.", name);
+                            manage(&sender, friends::rename_category(category_id, name), done);
+                        }
+                    },
+                );
+            }
+            DeleteCategory(category_id) => {
+                let name = category_name(category_id);
+                let sender = sender.clone();
+                dialogs::confirm(
+                    &format!("Delete {}?", name),
+                    "Its friends will be moved to the default group.",
+                    "Delete",
+                    move || {
+                        let done = format!("Deleted {}.", name);
+                        manage(&sender, friends::delete_category(category_id), done);
+                    },
+                );
+            }
             Search(keyword) => {
                 if keyword.is_empty() {
                     widgets
                         .scrolled_window
                         .set_child(Some(&widgets.friend_list));
                 } else {
-                    if let Err(err) = self.render_search_result(keyword) {
-                        sender.output(ContactMsg::PushToast(err.to_string()))
-                    }
+                    self.render_search_result(keyword);
                     widgets
                         .scrolled_window
                         .set_child(Some(&widgets.search_list));
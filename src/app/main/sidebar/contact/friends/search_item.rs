@@ -10,11 +10,36 @@ use tokio::task;
 
 use crate::db::fs::{download_user_avatar_file, get_user_avatar_path};
 use crate::db::sql::Friend;
+use crate::utils::search::{highlight, SearchHit};
 
 use super::FriendsMsg;
 
-impl FactoryComponent<ListBox, FriendsMsg> for Friend {
-    type InitParams = Friend;
+/// A friend found by the search, with the matched characters highlighted.
+#[derive(Debug)]
+pub struct FriendSearchItem {
+    pub friend: Friend,
+    remark_markup: String,
+    name_markup: String,
+}
+
+impl From<SearchHit<Friend>> for FriendSearchItem {
+    /// The names of the friends are indexed as `[remark, name]`.
+    fn from(hit: SearchHit<Friend>) -> Self {
+        let mut name_markup = highlight(&hit.item.name, &hit.names[1]);
+        if !hit.uin.is_empty() {
+            name_markup.push_str(" · ");
+            name_markup.push_str(&highlight(&hit.item.id.to_string(), &hit.uin));
+        }
+        FriendSearchItem {
+            remark_markup: highlight(&hit.item.remark, &hit.names[0]),
+            name_markup,
+            friend: hit.item,
+        }
+    }
+}
+
+impl FactoryComponent<ListBox, FriendsMsg> for FriendSearchItem {
+    type InitParams = FriendSearchItem;
     type Widgets = ();
     type Input = ();
     type Output = ();
@@ -49,7 +74,7 @@ impl FactoryComponent<ListBox, FriendsMsg> for Friend {
                 set_margin_bottom: 8,
                 #[name = "avatar"]
                 Avatar {
-                    set_text: Some(&self.name),
+                    set_text: Some(&self.friend.name),
                     set_show_initials: true,
                     set_size: 48,
                     set_margin_end: 8
@@ -60,12 +85,12 @@ impl FactoryComponent<ListBox, FriendsMsg> for Friend {
                     set_spacing: 8,
                     Label {
                         set_xalign: 0.0,
-                        set_text: self.remark.as_str(),
+                        set_markup: &self.remark_markup,
                         add_css_class: "heading",
                         set_ellipsize: EllipsizeMode::End,
                     },
                     Label {
-                        set_text: self.name.to_string().as_str(),
+                        set_markup: &self.name_markup,
                         add_css_class: "caption",
                         set_xalign: 0.0,
                         set_ellipsize: EllipsizeMode::End,
@@ -74,7 +99,7 @@ impl FactoryComponent<ListBox, FriendsMsg> for Friend {
             }
         }
 
-        let avatar_path = get_user_avatar_path(self.id);
+        let avatar_path = get_user_avatar_path(self.friend.id);
         if avatar_path.exists() {
             if let Ok(pixbuf) = Pixbuf::from_file_at_size(avatar_path, 48, 48) {
                 let image = Picture::for_pixbuf(&pixbuf);
@@ -83,7 +108,7 @@ impl FactoryComponent<ListBox, FriendsMsg> for Friend {
                 }
             }
         } else {
-            task::spawn(download_user_avatar_file(self.id));
+            task::spawn(download_user_avatar_file(self.friend.id));
         }
 
         root.append(&item);
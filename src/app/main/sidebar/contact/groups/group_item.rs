@@ -11,9 +11,38 @@ use tokio::task;
 use super::GroupsMsg;
 use crate::db::fs::{download_group_avatar_file, get_group_avatar_path};
 use crate::db::sql::Group;
+use crate::utils::search::{highlight, SearchHit};
 
-impl FactoryComponent<ListBox, GroupsMsg> for Group {
-    type InitParams = Group;
+/// A group in the list, with the characters matching the search highlighted.
+#[derive(Debug)]
+pub struct GroupItem {
+    pub group: Group,
+    name_markup: String,
+    id_markup: String,
+}
+
+impl From<Group> for GroupItem {
+    fn from(group: Group) -> Self {
+        GroupItem {
+            name_markup: highlight(&group.name, &[]),
+            id_markup: group.id.to_string(),
+            group,
+        }
+    }
+}
+
+impl From<SearchHit<Group>> for GroupItem {
+    fn from(hit: SearchHit<Group>) -> Self {
+        GroupItem {
+            name_markup: highlight(&hit.item.name, &hit.names[0]),
+            id_markup: highlight(&hit.item.id.to_string(), &hit.uin),
+            group: hit.item,
+        }
+    }
+}
+
+impl FactoryComponent<ListBox, GroupsMsg> for GroupItem {
+    type InitParams = GroupItem;
     type Widgets = ();
     type Input = ();
     type Output = ();
@@ -48,7 +77,7 @@ impl FactoryComponent<ListBox, GroupsMsg> for Group {
                 set_margin_bottom: 8,
                 #[name = "avatar"]
                 Avatar {
-                    set_text: Some(&self.name),
+                    set_text: Some(&self.group.name),
                     set_show_initials: true,
                     set_size: 48,
                     set_margin_end: 8
@@ -59,12 +88,12 @@ impl FactoryComponent<ListBox, GroupsMsg> for Group {
                     set_spacing: 8,
                     Label {
                         set_xalign: 0.0,
-                        set_text: self.name.as_str(),
+                        set_markup: &self.name_markup,
                         add_css_class: "heading",
                         set_ellipsize: EllipsizeMode::End,
                     },
                     Label {
-                        set_text: self.id.to_string().as_str(),
+                        set_markup: &self.id_markup,
                         add_css_class: "caption",
                         set_xalign: 0.0,
                         set_ellipsize: EllipsizeMode::End,
@@ -73,7 +102,7 @@ impl FactoryComponent<ListBox, GroupsMsg> for Group {
             }
         }
 
-        let avatar_path = get_group_avatar_path(self.id);
+        let avatar_path = get_group_avatar_path(self.group.id);
         if avatar_path.exists() {
             if let Ok(pixbuf) = Pixbuf::from_file_at_size(avatar_path, 48, 48) {
                 let image = Picture::for_pixbuf(&pixbuf);
@@ -82,7 +111,7 @@ impl FactoryComponent<ListBox, GroupsMsg> for Group {
                 }
             }
         } else {
-            task::spawn(download_group_avatar_file(self.id));
+            task::spawn(download_group_avatar_file(self.group.id));
         }
 
         root.append(&item);
@@ -10,10 +10,14 @@ use tokio::task;
 
 use super::ContactMsg;
 use crate::db::sql::{get_db, refresh_groups_list, Group};
+use crate::event::{self, AppEvent};
+use crate::utils::search::SearchIndex;
+use group_item::GroupItem;
 
 #[derive(Debug)]
 pub struct GroupsModel {
-    group_list: Option<FactoryVecDeque<ListBox, Group, GroupsMsg>>,
+    group_list: Option<FactoryVecDeque<ListBox, GroupItem, GroupsMsg>>,
+    search_index: SearchIndex<Group>,
     is_refresh_button_enabled: bool,
 }
 
@@ -34,8 +38,11 @@ impl GroupsModel {
             })?
             .map(|result| result.unwrap());
 
+        self.search_index = SearchIndex::default();
         for group in groups {
-            group_list.push_back(group);
+            self.search_index
+                .insert(group.clone(), group.id, &[&group.name]);
+            group_list.push_back(group.into());
         }
 
         group_list.render_changes();
@@ -43,32 +50,15 @@ impl GroupsModel {
     }
 
     fn search(&mut self, keyword: String) -> rusqlite::Result<()> {
+        if keyword.is_empty() {
+            return self.render_groups();
+        }
+
         let group_list = self.group_list.as_mut().unwrap();
         group_list.clear();
 
-        let conn = get_db();
-
-        let mut stmt = conn.prepare("Select id, name from groups")?;
-        let groups = stmt
-            .query_map([], |row| {
-                Ok(Group {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                })
-            })?
-            .map(|result| result.unwrap());
-
-        if keyword.is_empty() {
-            for group in groups {
-                group_list.push_back(group);
-            }
-        } else {
-            let keyword = keyword.to_lowercase();
-            let groups =
-                groups.filter(|group: &Group| group.name.to_lowercase().contains(&keyword));
-            for group in groups {
-                group_list.push_back(group);
-            }
+        for hit in self.search_index.search(&keyword) {
+            group_list.push_back(hit.into());
         }
 
         group_list.render_changes();
@@ -91,6 +81,8 @@ async fn refresh_groups(sender: ComponentSender<GroupsModel>) {
 pub enum GroupsMsg {
     Refresh,
     Render,
+    /// Render again without a toast, after a sync in the background.
+    Reload,
     Search(String),
     Select(i32),
 }
@@ -149,17 +141,23 @@ impl SimpleComponent for GroupsModel {
     ) -> ComponentParts<Self> {
         let mut model = GroupsModel {
             group_list: None,
+            search_index: SearchIndex::default(),
             is_refresh_button_enabled: true,
         };
         let widgets = view_output!();
 
-        let groups_list: FactoryVecDeque<ListBox, Group, GroupsMsg> =
+        let groups_list: FactoryVecDeque<ListBox, GroupItem, GroupsMsg> =
             FactoryVecDeque::new(widgets.groups_list.clone(), &sender.input);
 
         model.group_list = Some(groups_list);
 
         model.render_groups().unwrap();
 
+        event::forward(sender.input.clone(), |event| match event {
+            AppEvent::ContactsUpdated { groups: true, .. } => Some(GroupsMsg::Reload),
+            _ => None,
+        });
+
         ComponentParts { model, widgets }
     }
 
@@ -168,7 +166,7 @@ impl SimpleComponent for GroupsModel {
         match msg {
             Select(index) => {
                 let group_list = self.group_list.as_ref().unwrap();
-                let account = group_list.get(index as usize).id;
+                let account = group_list.get(index as usize).group.id;
                 sender.output(ContactMsg::SelectChatroom(account, true));
             }
             Refresh => {
@@ -184,6 +182,11 @@ impl SimpleComponent for GroupsModel {
                 }
                 self.is_refresh_button_enabled = true;
             }
+            Reload => {
+                if let Err(err) = self.render_groups() {
+                    log::warn!("Failed to render the groups list: {}", err);
+                }
+            }
             Search(keyword) => self.search(keyword).unwrap(),
         }
     }
@@ -13,6 +13,7 @@ use self::groups::GroupsModel;
 use friends::FriendsModel;
 
 use super::SidebarMsg;
+use crate::app::main::ChatKind;
 
 #[derive(Debug)]
 pub struct ContactModel {
@@ -23,6 +24,8 @@ pub struct ContactModel {
 #[derive(Debug)]
 pub enum ContactMsg {
     SelectChatroom(i64, bool),
+    ShowProfile(i64),
+    AddFriend,
     PushToast(String),
 }
 
@@ -78,8 +81,15 @@ impl SimpleComponent for ContactModel {
         use ContactMsg::*;
         match msg {
             SelectChatroom(account, is_group) => {
-                sender.output(SidebarMsg::SelectChatroom(account, is_group));
+                let kind = if is_group {
+                    ChatKind::Group
+                } else {
+                    ChatKind::Friend
+                };
+                sender.output(SidebarMsg::SelectChatroom(account, kind));
             }
+            ShowProfile(account) => sender.output(SidebarMsg::ShowProfile(account)),
+            AddFriend => sender.output(SidebarMsg::AddFriend),
             PushToast(msg) => {
                 sender.output(SidebarMsg::PushToast(msg));
             }
@@ -1,5 +1,7 @@
 mod chats;
 mod contact;
+mod own_profile;
+mod requests;
 
 use relm4::{
     adw, component::Controller, gtk, Component, ComponentController, ComponentParts,
@@ -7,24 +9,53 @@ use relm4::{
 };
 
 use adw::{prelude::*, HeaderBar, ViewStack, ViewSwitcherTitle};
-use gtk::{Box, Orientation};
+use gtk::{Box, Button, CheckButton, Image, MenuButton, Orientation, Popover, Separator};
 
-use super::MainMsg;
+use super::{ChatKind, MainMsg};
+use crate::connection::{connection_state, ConnectionState};
+use crate::db::account::{saved_status, OnlineStatus};
+use crate::event::{self, AppEvent};
 use chats::{ChatsModel, ChatsMsg};
 use contact::ContactModel;
+use requests::RequestsModel;
 
 #[derive(Debug)]
 pub(crate) struct SidebarModel {
     chats: Controller<ChatsModel>,
     contact: Controller<ContactModel>,
+    requests: Controller<RequestsModel>,
+    connection_state: ConnectionState,
+    /// Why the connection was lost, or failed to be recovered.
+    connection_error: Option<String>,
+    status: OnlineStatus,
+    status_buttons: Vec<(OnlineStatus, CheckButton)>,
+}
+
+impl SidebarModel {
+    fn connection_tooltip(&self) -> String {
+        match &self.connection_error {
+            Some(error) => format!("{}\n{}", self.connection_state.label(), error),
+            None => self.connection_state.label().to_string(),
+        }
+    }
 }
 
 #[derive(Debug)]
 pub enum SidebarMsg {
-    SelectChatroom(i64, bool),
-    UpdateChatItem(i64, bool, String),
-    InsertChatItem(i64, bool, String),
+    SelectChatroom(i64, ChatKind),
+    ShowProfile(i64),
+    AddFriend,
+    UpdateChatItem(i64, ChatKind, String),
+    InsertChatItem(i64, ChatKind, String),
+    RenameChatItem(i64, ChatKind, String),
     PushToast(String),
+    ConnectionChanged(ConnectionState, Option<String>),
+    SetStatus(OnlineStatus),
+    StatusChanged(OnlineStatus),
+    StatusFailed(String),
+    EditProfile,
+    /// Open the editor with our nickname and signature.
+    ShowProfileEditor(String, String),
 }
 
 #[relm4::component(pub)]
@@ -43,6 +74,37 @@ impl SimpleComponent for SidebarModel {
             HeaderBar {
                 set_show_start_title_buttons: false,
                 set_show_end_title_buttons: false,
+                pack_start = &Image {
+                    #[watch]
+                    set_icon_name: Some(model.connection_state.icon_name()),
+                    #[watch]
+                    set_tooltip_text: Some(&model.connection_tooltip()),
+                },
+                pack_end = &MenuButton {
+                    #[watch]
+                    set_icon_name: model.status.icon_name(),
+                    #[watch]
+                    set_tooltip_text: Some(model.status.label()),
+                    set_popover: popover = Some(&Popover) {
+                        set_child = Some(&Box) {
+                            set_orientation: Orientation::Vertical,
+                            set_spacing: 4,
+                            #[name = "status_box"]
+                            Box {
+                                set_orientation: Orientation::Vertical,
+                            },
+                            Separator {},
+                            Button {
+                                set_label: "Edit Profile…",
+                                add_css_class: "flat",
+                                connect_clicked[sender, popover] => move |_| {
+                                    popover.popdown();
+                                    sender.input(SidebarMsg::EditProfile);
+                                },
+                            },
+                        },
+                    },
+                },
                 set_title_widget = Some(&ViewSwitcherTitle) {
                     set_title: "Sidebar",
                     set_stack: Some(&stack)
@@ -60,6 +122,8 @@ impl SimpleComponent for SidebarModel {
         root: &Self::Root,
         sender: &ComponentSender<Self>,
     ) -> ComponentParts<Self> {
+        let (connection_state, connection_error) = connection_state();
+        let status = saved_status();
         let model = SidebarModel {
             chats: ChatsModel::builder()
                 .launch(())
@@ -67,16 +131,36 @@ impl SimpleComponent for SidebarModel {
             contact: ContactModel::builder()
                 .launch(())
                 .forward(&sender.input, |message| message),
+            requests: RequestsModel::builder()
+                .launch(())
+                .forward(&sender.input, |message| message),
+            connection_state,
+            connection_error,
+            status,
+            status_buttons: own_profile::status_buttons(status, sender),
         };
         let widgets = view_output!();
 
+        for (_, button) in model.status_buttons.iter() {
+            widgets.status_box.append(button);
+        }
+
+        event::forward(sender.input.clone(), |event| match event {
+            AppEvent::ConnectionChanged { state, error } => {
+                Some(SidebarMsg::ConnectionChanged(state, error))
+            }
+            _ => None,
+        });
+
         let stack: &ViewStack = &widgets.stack;
 
         let chats = stack.add_titled(model.chats.widget(), None, "Chats");
         let contact = stack.add_titled(model.contact.widget(), None, "Contact");
+        let requests = stack.add_titled(model.requests.widget(), None, "Requests");
 
         chats.set_icon_name(Some("chat-symbolic"));
         contact.set_icon_name(Some("address-book-symbolic"));
+        requests.set_icon_name(Some("mail-unread-symbolic"));
 
         ComponentParts { model, widgets }
     }
@@ -84,20 +168,48 @@ impl SimpleComponent for SidebarModel {
     fn update(&mut self, msg: SidebarMsg, sender: &ComponentSender<Self>) {
         use SidebarMsg::*;
         match msg {
-            SelectChatroom(account, is_group) => {
-                sender.output(MainMsg::SelectChatroom(account, is_group));
+            SelectChatroom(account, kind) => {
+                sender.output(MainMsg::SelectChatroom(account, kind));
             }
-            UpdateChatItem(account, is_group, last_message) => {
+            ShowProfile(account) => sender.output(MainMsg::ShowProfile(account)),
+            AddFriend => sender.output(MainMsg::ShowAddFriend),
+            UpdateChatItem(account, kind, last_message) => {
                 self.chats
                     .sender()
-                    .send(ChatsMsg::UpdateChatItem(account, is_group, last_message));
+                    .send(ChatsMsg::UpdateChatItem(account, kind, last_message));
             }
-            InsertChatItem(account, is_group, last_message) => {
+            InsertChatItem(account, kind, last_message) => {
                 self.chats
                     .sender()
-                    .send(ChatsMsg::InsertChatItem(account, is_group, last_message));
+                    .send(ChatsMsg::InsertChatItem(account, kind, last_message));
+            }
+            RenameChatItem(account, kind, name) => {
+                self.chats
+                    .sender()
+                    .send(ChatsMsg::RenameChatItem(account, kind, name));
             }
             PushToast(message) => sender.output(MainMsg::PushToast(message)),
+            ConnectionChanged(state, error) => {
+                self.connection_state = state;
+                self.connection_error = error;
+            }
+            SetStatus(status) => {
+                if status != self.status {
+                    own_profile::set_status(status, sender);
+                }
+            }
+            StatusChanged(status) => self.status = status,
+            StatusFailed(error) => {
+                // Select the status we still have, which sends nothing.
+                for (status, button) in self.status_buttons.iter() {
+                    button.set_active(*status == self.status);
+                }
+                sender.output(MainMsg::PushToast(error));
+            }
+            EditProfile => own_profile::load_profile(sender),
+            ShowProfileEditor(nickname, signature) => {
+                own_profile::edit_profile(&nickname, &signature, sender);
+            }
         }
     }
 }
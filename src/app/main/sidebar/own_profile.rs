@@ -0,0 +1,143 @@
+use relm4::{gtk, ComponentSender};
+
+use gtk::prelude::*;
+use gtk::{
+    Box, ButtonsType, CheckButton, Entry, Label, MessageDialog, MessageType, Orientation,
+    ResponseType,
+};
+use tokio::task;
+
+use crate::db::account::{self, OnlineStatus};
+use crate::global::WINDOW;
+use crate::handler::{ACCOUNT, CLIENT};
+
+use super::{SidebarModel, SidebarMsg};
+
+/// A radio button for each status, selecting `selected`.
+pub(super) fn status_buttons(
+    selected: OnlineStatus,
+    sender: &ComponentSender<SidebarModel>,
+) -> Vec<(OnlineStatus, CheckButton)> {
+    let mut buttons: Vec<(OnlineStatus, CheckButton)> = Vec::new();
+    for status in OnlineStatus::ALL {
+        let button = CheckButton::with_label(status.label());
+        if let Some((_, first)) = buttons.first() {
+            button.set_group(Some(first));
+        }
+        button.set_active(status == selected);
+        let sender = sender.clone();
+        button.connect_toggled(move |button| {
+            if button.is_active() {
+                sender.input(SidebarMsg::SetStatus(status));
+            }
+        });
+        buttons.push((status, button));
+    }
+    buttons
+}
+
+pub(super) fn set_status(status: OnlineStatus, sender: &ComponentSender<SidebarModel>) {
+    let sender = sender.clone();
+    task::spawn(async move {
+        match account::set_status(status).await {
+            Ok(()) => sender.input(SidebarMsg::StatusChanged(status)),
+            Err(err) => sender.input(SidebarMsg::StatusFailed(format!(
+                "Failed to change the status: {}",
+                err
+            ))),
+        }
+    });
+}
+
+/// Fetch our nickname and signature to start the editor from.
+pub(super) fn load_profile(sender: &ComponentSender<SidebarModel>) {
+    let sender = sender.clone();
+    task::spawn(async move {
+        let client = CLIENT.get().unwrap();
+        let account = *ACCOUNT.get().unwrap();
+        match client.get_summary_info(account).await {
+            Ok(info) => sender.input(SidebarMsg::ShowProfileEditor(info.nickname, info.sign)),
+            Err(err) => sender.input(SidebarMsg::PushToast(format!(
+                "Failed to load the profile: {}",
+                err
+            ))),
+        }
+    });
+}
+
+/// Send each changed field, reporting the ones which fail.
+async fn save_profile(
+    nickname: Option<String>,
+    signature: Option<String>,
+    sender: ComponentSender<SidebarModel>,
+) {
+    let mut failed = false;
+    if let Some(nickname) = nickname {
+        if let Err(err) = account::set_nickname(nickname).await {
+            failed = true;
+            sender.input(SidebarMsg::PushToast(format!(
+                "Failed to change the nickname: {}",
+                err
+            )));
+        }
+    }
+    if let Some(signature) = signature {
+        if let Err(err) = account::set_signature(signature).await {
+            failed = true;
+            sender.input(SidebarMsg::PushToast(format!(
+                "Failed to change the signature: {}",
+                err
+            )));
+        }
+    }
+    if !failed {
+        sender.input(SidebarMsg::PushToast("Profile updated".to_string()));
+    }
+}
+
+fn labeled_entry(area: &Box, label: &str, text: &str) -> Entry {
+    let label = Label::builder().label(label).xalign(0.0).build();
+    let entry = Entry::builder().text(text).activates_default(true).build();
+    area.append(&label);
+    area.append(&entry);
+    entry
+}
+
+pub(super) fn edit_profile(
+    nickname: &str,
+    signature: &str,
+    sender: &ComponentSender<SidebarModel>,
+) {
+    let dialog = MessageDialog::builder()
+        .transient_for(&WINDOW.get().unwrap().window)
+        .modal(true)
+        .message_type(MessageType::Question)
+        .buttons(ButtonsType::None)
+        .text("Edit Profile")
+        .build();
+    dialog.add_button("Cancel", ResponseType::Cancel);
+    dialog.add_button("Save", ResponseType::Accept);
+    dialog.set_default_response(ResponseType::Accept);
+
+    let area: Box = dialog.message_area().downcast().unwrap();
+    area.set_orientation(Orientation::Vertical);
+    let nickname_entry = labeled_entry(&area, "Nickname", nickname);
+    let signature_entry = labeled_entry(&area, "Signature", signature);
+
+    let (nickname, signature) = (nickname.to_string(), signature.to_string());
+    let sender = sender.clone();
+    dialog.connect_response(move |dialog, response| {
+        if response == ResponseType::Accept {
+            // Only the changed fields are sent.
+            let new_nickname = nickname_entry.buffer().text();
+            let new_nickname = (new_nickname != nickname).then(|| new_nickname);
+            let new_signature = signature_entry.buffer().text();
+            let new_signature = (new_signature != signature).then(|| new_signature);
+            if new_nickname.is_some() || new_signature.is_some() {
+                task::spawn(save_profile(new_nickname, new_signature, sender.clone()));
+            }
+        }
+        dialog.close();
+    });
+    dialog.present();
+}
@@ -0,0 +1,350 @@
+mod request_item;
+
+use std::collections::HashSet;
+use std::mem::{self, Discriminant};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use relm4::factory::{DynamicIndex, FactoryVecDeque};
+use relm4::{adw, gtk, ComponentParts, ComponentSender, SimpleComponent, WidgetPlus};
+
+use adw::prelude::*;
+use gtk::{Box, Button, Label, ListBox, Orientation, ScrolledWindow};
+use ricq::structs::{JoinGroupRequest, NewFriendRequest, SelfInvited};
+use ricq::RQResult;
+use tokio::task;
+
+use super::SidebarMsg;
+use crate::event::{self, AppEvent};
+use crate::handler::CLIENT;
+
+#[derive(Debug, Clone)]
+pub(crate) enum RequestKind {
+    Friend,
+    /// Somebody wants to join a group we admin.
+    JoinGroup {
+        group_id: i64,
+        group_name: String,
+    },
+    /// We are invited to a group.
+    Invitation {
+        group_id: i64,
+        group_name: String,
+    },
+}
+
+/// A pending friend request, group join request or group invitation.
+#[derive(Debug, Clone)]
+pub(crate) struct Request {
+    pub seq: i64,
+    /// In seconds since the epoch.
+    pub time: i64,
+    /// The one who sent the request or the invitation.
+    pub requester_id: i64,
+    pub requester_name: String,
+    pub message: String,
+    pub kind: RequestKind,
+}
+
+/// Friend requests and group system messages are numbered separately, so a
+/// request is identified by its kind and seq.
+pub(crate) type RequestKey = (Discriminant<RequestKind>, i64);
+
+impl Request {
+    fn key(&self) -> RequestKey {
+        (mem::discriminant(&self.kind), self.seq)
+    }
+
+    fn from_event(event: AppEvent) -> Option<Self> {
+        let request = match event {
+            AppEvent::NewFriendRequest {
+                seq,
+                requester_id,
+                requester_nickname,
+                message,
+            } => Request {
+                seq,
+                time: now(),
+                requester_id,
+                requester_name: requester_nickname,
+                message,
+                kind: RequestKind::Friend,
+            },
+            AppEvent::GroupRequest {
+                seq,
+                time,
+                group_id,
+                group_name,
+                requester_id,
+                requester_nickname,
+                message,
+            } => Request {
+                seq,
+                time,
+                requester_id,
+                requester_name: requester_nickname,
+                message,
+                kind: RequestKind::JoinGroup {
+                    group_id,
+                    group_name,
+                },
+            },
+            AppEvent::SelfInvited {
+                seq,
+                time,
+                group_id,
+                group_name,
+                invitor_id,
+                invitor_nickname,
+            } => Request {
+                seq,
+                time,
+                requester_id: invitor_id,
+                requester_name: invitor_nickname,
+                message: String::new(),
+                kind: RequestKind::Invitation {
+                    group_id,
+                    group_name,
+                },
+            },
+            _ => return None,
+        };
+
+        Some(request)
+    }
+}
+
+/// Friend requests come without a time, so they are stamped when received.
+fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+impl From<NewFriendRequest> for Request {
+    fn from(request: NewFriendRequest) -> Self {
+        Request {
+            seq: request.msg_seq,
+            time: now(),
+            requester_id: request.req_uin,
+            requester_name: request.req_nick,
+            message: request.message,
+            kind: RequestKind::Friend,
+        }
+    }
+}
+
+impl From<JoinGroupRequest> for Request {
+    fn from(request: JoinGroupRequest) -> Self {
+        Request {
+            seq: request.msg_seq,
+            time: request.msg_time,
+            requester_id: request.req_uin,
+            requester_name: request.req_nick,
+            message: request.message,
+            kind: RequestKind::JoinGroup {
+                group_id: request.group_code,
+                group_name: request.group_name,
+            },
+        }
+    }
+}
+
+impl From<SelfInvited> for Request {
+    fn from(invitation: SelfInvited) -> Self {
+        Request {
+            seq: invitation.msg_seq,
+            time: invitation.msg_time,
+            requester_id: invitation.invitor_uin,
+            requester_name: invitation.invitor_nick,
+            message: String::new(),
+            kind: RequestKind::Invitation {
+                group_id: invitation.group_code,
+                group_name: invitation.group_name,
+            },
+        }
+    }
+}
+
+async fn fetch_requests(sender: ComponentSender<RequestsModel>) {
+    let client = CLIENT.get().unwrap();
+    let mut requests: Vec<Request> = Vec::new();
+
+    match client.get_friend_system_messages().await {
+        Ok(messages) => requests.extend(messages.requests.into_iter().map(Request::from)),
+        Err(err) => sender.output(SidebarMsg::PushToast(err.to_string())),
+    }
+    match client.get_all_group_system_messages().await {
+        Ok(messages) => {
+            requests.extend(messages.self_invited.into_iter().map(Request::from));
+            requests.extend(messages.join_group_requests.into_iter().map(Request::from));
+        }
+        Err(err) => sender.output(SidebarMsg::PushToast(err.to_string())),
+    }
+
+    requests.sort_by(|a, b| b.time.cmp(&a.time));
+    sender.input(RequestsMsg::Render(requests));
+}
+
+async fn solve_request(request: &Request, accept: bool) -> RQResult<()> {
+    let client = CLIENT.get().unwrap();
+    match request.kind {
+        RequestKind::Friend => {
+            client
+                .solve_friend_system_message(request.seq, request.requester_id, accept)
+                .await
+        }
+        RequestKind::JoinGroup { group_id, .. } | RequestKind::Invitation { group_id, .. } => {
+            let is_invitation = matches!(request.kind, RequestKind::Invitation { .. });
+            client
+                .solve_group_system_message(
+                    request.seq,
+                    request.requester_id,
+                    group_id,
+                    false,
+                    is_invitation,
+                    accept,
+                    false,
+                    String::new(),
+                )
+                .await
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct RequestsModel {
+    requests: FactoryVecDeque<ListBox, Request, RequestsMsg>,
+    /// The requests being accepted or rejected, which are not solved twice.
+    solving: HashSet<RequestKey>,
+    is_refresh_button_enabled: bool,
+}
+
+impl RequestsModel {
+    fn remove_request(&mut self, key: RequestKey) {
+        for i in 0..self.requests.len() {
+            if self.requests.get(i).key() == key {
+                self.requests.remove(i);
+                break;
+            }
+        }
+        self.requests.render_changes();
+    }
+}
+
+#[derive(Debug)]
+pub enum RequestsMsg {
+    Refresh,
+    Render(Vec<Request>),
+    Insert(Request),
+    Solve(DynamicIndex, bool),
+    Solved(RequestKey),
+    Failed(RequestKey, String),
+}
+
+#[relm4::component(pub)]
+impl SimpleComponent for RequestsModel {
+    type Input = RequestsMsg;
+    type Output = SidebarMsg;
+    type Widgets = RequestsWidgets;
+    type InitParams = ();
+
+    view! {
+        #[root]
+        requests = Box {
+            set_orientation: Orientation::Vertical,
+            Box {
+                set_margin_all: 8,
+                set_spacing: 8,
+                Button {
+                    #[watch]
+                    set_sensitive: model.is_refresh_button_enabled,
+                    set_tooltip_text: Some("Refresh requests"),
+                    set_icon_name: "view-refresh-symbolic",
+                    connect_clicked[sender] => move |_| {
+                        sender.input(RequestsMsg::Refresh);
+                    },
+                },
+                Label {
+                    set_label: "Friend requests and invitations",
+                    add_css_class: "dim-label",
+                },
+            },
+            ScrolledWindow {
+                set_vexpand: true,
+                set_child: requests_list = Some(&ListBox) {
+                    set_css_classes: &["navigation-sidebar"],
+                    set_selection_mode: gtk::SelectionMode::None,
+                }
+            }
+        }
+    }
+
+    fn init(
+        _init_params: (),
+        root: &Self::Root,
+        sender: &ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let widgets = view_output!();
+
+        let requests: FactoryVecDeque<ListBox, Request, RequestsMsg> =
+            FactoryVecDeque::new(widgets.requests_list.clone(), &sender.input);
+
+        event::forward(sender.input_sender().clone(), |event| {
+            Request::from_event(event).map(RequestsMsg::Insert)
+        });
+
+        let model = RequestsModel {
+            requests,
+            solving: HashSet::new(),
+            is_refresh_button_enabled: true,
+        };
+        sender.input(RequestsMsg::Refresh);
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update(&mut self, msg: RequestsMsg, sender: &ComponentSender<Self>) {
+        use RequestsMsg::*;
+        match msg {
+            Refresh => {
+                self.is_refresh_button_enabled = false;
+                task::spawn(fetch_requests(sender.clone()));
+            }
+            Render(requests) => {
+                self.requests.clear();
+                for request in requests {
+                    self.requests.push_back(request);
+                }
+                self.requests.render_changes();
+                self.is_refresh_button_enabled = true;
+            }
+            Insert(request) => {
+                self.remove_request(request.key());
+                self.requests.push_front(request);
+                self.requests.render_changes();
+            }
+            Solve(index, accept) => {
+                let request = self.requests.get(index.current_index()).clone();
+                if !self.solving.insert(request.key()) {
+                    return;
+                }
+                let sender = sender.clone();
+                task::spawn(async move {
+                    match solve_request(&request, accept).await {
+                        Ok(_) => sender.input(Solved(request.key())),
+                        Err(err) => sender.input(Failed(request.key(), err.to_string())),
+                    }
+                });
+            }
+            Solved(key) => {
+                self.solving.remove(&key);
+                self.remove_request(key);
+            }
+            Failed(key, err) => {
+                self.solving.remove(&key);
+                sender.output(SidebarMsg::PushToast(err));
+            }
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use relm4::factory::{DynamicIndex, FactoryComponent};
+use relm4::{adw, gtk, Sender};
+
+use adw::{prelude::*, Avatar};
+use gtk::gdk_pixbuf::Pixbuf;
+use gtk::glib::DateTime;
+use gtk::pango::WrapMode;
+use gtk::{Align, Box, Button, Label, ListBox, ListBoxRow, Orientation, Picture};
+
+use tokio::task;
+
+use super::{Request, RequestKind, RequestsMsg};
+use crate::db::fs::{download_user_avatar_file, get_user_avatar_path};
+
+fn format_time(time: i64) -> String {
+    DateTime::from_unix_local(time)
+        .and_then(|time| time.format("%Y-%m-%d %H:%M"))
+        .map(|time| time.to_string())
+        .unwrap_or_default()
+}
+
+impl FactoryComponent<ListBox, RequestsMsg> for Request {
+    type InitParams = Request;
+    type Widgets = ();
+    type Input = ();
+    type Output = RequestsMsg;
+    type Command = ();
+    type CommandOutput = ();
+    type Root = Box;
+
+    fn init_model(
+        init_params: Self::InitParams,
+        _index: &DynamicIndex,
+        _input: &Sender<Self::Input>,
+        _output: &Sender<Self::Output>,
+    ) -> Self {
+        init_params
+    }
+
+    fn init_root(&self) -> Self::Root {
+        Box::default()
+    }
+
+    fn init_widgets(
+        &mut self,
+        index: &DynamicIndex,
+        root: &Self::Root,
+        _returned_widget: &ListBoxRow,
+        _input: &Sender<Self::Input>,
+        output: &Sender<Self::Output>,
+    ) -> Self::Widgets {
+        let title = match &self.kind {
+            RequestKind::Friend => format!("{} wants to add you as a friend", self.requester_name),
+            RequestKind::JoinGroup { group_name, .. } => {
+                format!("{} wants to join {}", self.requester_name, group_name)
+            }
+            RequestKind::Invitation { group_name, .. } => {
+                format!("{} invited you to join {}", self.requester_name, group_name)
+            }
+        };
+
+        relm4::view! {
+            item = Box {
+                set_margin_top: 8,
+                set_margin_bottom: 8,
+                #[name = "avatar"]
+                Avatar {
+                    set_text: Some(&self.requester_name),
+                    set_show_initials: true,
+                    set_size: 48,
+                    set_margin_end: 8,
+                    set_valign: Align::Start,
+                },
+                Box {
+                    set_orientation: Orientation::Vertical,
+                    set_hexpand: true,
+                    set_spacing: 4,
+                    Label {
+                        set_xalign: 0.0,
+                        set_text: &title,
+                        set_wrap: true,
+                        set_wrap_mode: WrapMode::WordChar,
+                        add_css_class: "heading",
+                    },
+                    Label {
+                        set_xalign: 0.0,
+                        set_text: &self.message,
+                        set_visible: !self.message.is_empty(),
+                        set_wrap: true,
+                        set_wrap_mode: WrapMode::WordChar,
+                    },
+                    Label {
+                        set_xalign: 0.0,
+                        set_text: &format_time(self.time),
+                        set_css_classes: &["caption", "dim-label"],
+                    },
+                    Box {
+                        set_spacing: 8,
+                        set_halign: Align::End,
+                        Button {
+                            set_label: "Reject",
+                            connect_clicked[output, index] => move |_| {
+                                output.send(RequestsMsg::Solve(index.clone(), false));
+                            },
+                        },
+                        Button {
+                            set_label: "Accept",
+                            add_css_class: "suggested-action",
+                            connect_clicked[output, index] => move |_| {
+                                output.send(RequestsMsg::Solve(index.clone(), true));
+                            },
+                        },
+                    },
+                },
+            }
+        }
+
+        let avatar_path = get_user_avatar_path(self.requester_id);
+        if avatar_path.exists() {
+            if let Ok(pixbuf) = Pixbuf::from_file_at_size(avatar_path, 48, 48) {
+                let image = Picture::for_pixbuf(&pixbuf);
+                if let Some(paintable) = image.paintable() {
+                    avatar.set_custom_image(Some(&paintable));
+                }
+            }
+        } else {
+            task::spawn(download_user_avatar_file(self.requester_id));
+        }
+
+        root.append(&item);
+    }
+
+    fn output_to_parent_msg(output: Self::Output) -> Option<RequestsMsg> {
+        Some(output)
+    }
+}
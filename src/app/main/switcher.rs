@@ -0,0 +1,309 @@
+use std::cmp::Reverse;
+
+use relm4::{adw, gtk, Component, ComponentParts, ComponentSender, WidgetPlus};
+
+use adw::{prelude::*, Window};
+use gtk::gdk::Key;
+use gtk::pango::EllipsizeMode;
+use gtk::{
+    Align, Box, EventControllerKey, Image, Inhibit, Label, ListBox, Orientation, ScrolledWindow,
+    SearchEntry,
+};
+
+use crate::db::sql::{get_friends, get_groups};
+use crate::utils::search::{highlight, SearchHit, SearchIndex};
+
+use super::{ChatKind, MainMsg};
+
+/// How much a recent chat is preferred over an equally good match. Less
+/// than the gap between two kinds of matches.
+const RECENT_BONUS: i32 = 10;
+
+#[derive(Debug, Clone)]
+pub(crate) struct Target {
+    account: i64,
+    kind: ChatKind,
+    name: String,
+    recent: bool,
+}
+
+impl Target {
+    fn icon_name(&self) -> &'static str {
+        match self.kind {
+            ChatKind::Friend => "person2-symbolic",
+            ChatKind::Group => "people-symbolic",
+            ChatKind::Temp { .. } => "chat-symbolic",
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match (self.recent, self.kind) {
+            (true, ChatKind::Friend) => "Recent chat · Friend",
+            (true, ChatKind::Group) => "Recent chat · Group",
+            (_, ChatKind::Temp { .. }) => "Recent chat · Temp",
+            (false, ChatKind::Friend) => "Friend",
+            (false, ChatKind::Group) => "Group",
+        }
+    }
+}
+
+/// A command palette to jump to any chat, friend or group by typing.
+pub(crate) struct SwitcherModel {
+    window: Window,
+    index: SearchIndex<Target>,
+    /// The recent chats, shown before anything is typed.
+    recent_chats: Vec<Target>,
+    results: Vec<SearchHit<Target>>,
+}
+
+impl SwitcherModel {
+    fn search(&mut self, keyword: &str) {
+        self.results = if keyword.trim().is_empty() {
+            self.recent_chats
+                .iter()
+                .map(|target| SearchHit {
+                    item: target.clone(),
+                    score: 0,
+                    names: vec![Vec::new()],
+                    uin: Vec::new(),
+                })
+                .collect()
+        } else {
+            let mut results = self.index.search(keyword);
+            results.sort_by_key(|hit| Reverse(hit.score + hit.item.recent as i32 * RECENT_BONUS));
+            results
+        };
+    }
+
+    fn render(&self, widgets: &mut SwitcherWidgets) {
+        let list = &widgets.results_list;
+        while let Some(row) = list.row_at_index(0) {
+            list.remove(&row);
+        }
+        for hit in self.results.iter() {
+            list.append(&result_row(hit));
+        }
+        if let Some(row) = list.row_at_index(0) {
+            list.select_row(Some(&row));
+        }
+    }
+}
+
+pub(crate) struct Payload {
+    pub window: Window,
+    /// The chats in the sidebar, with their names.
+    pub recent_chats: Vec<(i64, ChatKind, String)>,
+}
+
+#[derive(Debug)]
+pub(crate) enum SwitcherMsg {
+    Search(String),
+    /// Move the selection by the given number of rows.
+    Move(i32),
+    /// Open the selected result, or the first one.
+    Activate,
+    Open(i32),
+}
+
+fn result_row(hit: &SearchHit<Target>) -> Box {
+    let target = &hit.item;
+    let mut description = target.description().to_string();
+    if !hit.uin.is_empty() {
+        description.push_str(" · ");
+        description.push_str(&highlight(&target.account.to_string(), &hit.uin));
+    }
+
+    relm4::view! {
+        row = Box {
+            set_margin_all: 8,
+            set_spacing: 12,
+            Image {
+                set_icon_name: Some(target.icon_name()),
+            },
+            Box {
+                set_orientation: Orientation::Vertical,
+                set_halign: Align::Start,
+                Label {
+                    set_xalign: 0.0,
+                    set_markup: &highlight(&target.name, &hit.names[0]),
+                    set_ellipsize: EllipsizeMode::End,
+                    add_css_class: "heading",
+                },
+                Label {
+                    set_xalign: 0.0,
+                    set_markup: &description,
+                    set_css_classes: &["caption", "dim-label"],
+                },
+            },
+        }
+    }
+    row
+}
+
+#[derive(Debug)]
+pub(crate) struct SwitcherWidgets {
+    results_list: ListBox,
+}
+
+impl Component for SwitcherModel {
+    type Input = SwitcherMsg;
+    type Output = MainMsg;
+    type InitParams = Payload;
+    type Widgets = SwitcherWidgets;
+    type Root = Box;
+    type CommandOutput = ();
+
+    fn init_root() -> Self::Root {
+        Box::new(Orientation::Vertical, 0)
+    }
+
+    fn init(
+        params: Self::InitParams,
+        root: &Self::Root,
+        sender: &ComponentSender<Self>,
+    ) -> ComponentParts<Self> {
+        let recent_chats: Vec<Target> = params
+            .recent_chats
+            .into_iter()
+            .map(|(account, kind, name)| Target {
+                account,
+                kind,
+                name,
+                recent: true,
+            })
+            .collect();
+        let is_recent = |account: i64, kind: ChatKind| {
+            recent_chats
+                .iter()
+                .any(|target| target.account == account && target.kind == kind)
+        };
+
+        let mut index = SearchIndex::default();
+        for target in recent_chats.iter() {
+            index.insert(target.clone(), target.account, &[&target.name]);
+        }
+        for friend in get_friends().unwrap_or_default() {
+            if !is_recent(friend.id, ChatKind::Friend) {
+                let target = Target {
+                    account: friend.id,
+                    kind: ChatKind::Friend,
+                    name: friend.remark,
+                    recent: false,
+                };
+                index.insert(target.clone(), friend.id, &[&target.name]);
+            }
+        }
+        for group in get_groups().unwrap_or_default() {
+            if !is_recent(group.id, ChatKind::Group) {
+                let target = Target {
+                    account: group.id,
+                    kind: ChatKind::Group,
+                    name: group.name,
+                    recent: false,
+                };
+                index.insert(target.clone(), group.id, &[&target.name]);
+            }
+        }
+
+        let mut model = SwitcherModel {
+            window: params.window,
+            index,
+            recent_chats,
+            results: Vec::new(),
+        };
+        model.search("");
+
+        let key_controller = EventControllerKey::new();
+        {
+            let sender = sender.clone();
+            key_controller.connect_key_pressed(move |_, key, _, _| {
+                let offset = if key == Key::Down {
+                    1
+                } else if key == Key::Up {
+                    -1
+                } else {
+                    return Inhibit(false);
+                };
+                sender.input(SwitcherMsg::Move(offset));
+                Inhibit(true)
+            });
+        }
+
+        let window = model.window.clone();
+        relm4::view! {
+            search_entry = SearchEntry {
+                set_margin_all: 12,
+                set_placeholder_text: Some("Jump to a chat, friend or group…"),
+                add_controller: &key_controller,
+                connect_search_changed[sender] => move |entry| {
+                    sender.input(SwitcherMsg::Search(entry.text().to_string()));
+                },
+                connect_activate[sender] => move |_| {
+                    sender.input(SwitcherMsg::Activate);
+                },
+                connect_stop_search[window] => move |_| {
+                    window.close();
+                },
+            }
+        }
+
+        relm4::view! {
+            scrolled_window = ScrolledWindow {
+                set_vexpand: true,
+                set_child: results_list = Some(&ListBox) {
+                    set_css_classes: &["navigation-sidebar"],
+                    connect_row_activated[sender] => move |_, row| {
+                        sender.input(SwitcherMsg::Open(row.index()));
+                    },
+                }
+            }
+        }
+
+        root.append(&search_entry);
+        root.append(&scrolled_window);
+
+        let mut widgets = SwitcherWidgets { results_list };
+        model.render(&mut widgets);
+        search_entry.grab_focus();
+
+        ComponentParts { model, widgets }
+    }
+
+    fn update_with_view(
+        &mut self,
+        widgets: &mut Self::Widgets,
+        msg: Self::Input,
+        sender: &ComponentSender<Self>,
+    ) {
+        use SwitcherMsg::*;
+        match msg {
+            Search(keyword) => {
+                self.search(&keyword);
+                self.render(widgets);
+            }
+            Move(offset) => {
+                let list = &widgets.results_list;
+                let current = list.selected_row().map(|row| row.index()).unwrap_or(0);
+                let last = self.results.len() as i32 - 1;
+                if let Some(row) = list.row_at_index((current + offset).clamp(0, last.max(0))) {
+                    list.select_row(Some(&row));
+                }
+            }
+            Activate => {
+                let selected = widgets
+                    .results_list
+                    .selected_row()
+                    .map(|row| row.index())
+                    .unwrap_or(0);
+                sender.input(Open(selected));
+            }
+            Open(index) => {
+                if let Some(hit) = self.results.get(index as usize) {
+                    let target = &hit.item;
+                    sender.output(MainMsg::SelectChatroom(target.account, target.kind));
+                    self.window.close();
+                }
+            }
+        }
+    }
+}
@@ -11,9 +11,10 @@ use gtk::{Box, Stack, StackTransitionType};
 
 use crate::{
     actions::create_gactions,
+    event::{self, AppEvent},
     global::{SharedWindow, WINDOW},
 };
-use login::LoginPageModel;
+use login::{LoginPageModel, LoginPageMsg};
 use main::MainPageModel;
 
 pub struct AppModel {
@@ -30,6 +31,8 @@ enum Page {
 #[derive(Debug)]
 pub enum AppMessage {
     LoginSuccessful,
+    /// Back to the login page, showing why the session ended.
+    LoggedOut(String),
 }
 
 #[relm4::component(pub)]
@@ -58,6 +61,10 @@ impl SimpleComponent for AppModel {
     fn update(&mut self, msg: Self::Input, _sender: &ComponentSender<Self>) {
         match msg {
             AppMessage::LoginSuccessful => self.page = Page::Main,
+            AppMessage::LoggedOut(reason) => {
+                self.page = Page::Login;
+                self.login.emit(LoginPageMsg::LoginFailed(reason));
+            }
         }
     }
 
@@ -82,6 +89,11 @@ impl SimpleComponent for AppModel {
         };
         let widgets = view_output!();
 
+        event::forward(sender.input.clone(), |event| match event {
+            AppEvent::LoggedOut { reason } => Some(AppMessage::LoggedOut(reason)),
+            _ => None,
+        });
+
         let actions = create_gactions(root.clone());
         root.insert_action_group("menu", Some(&actions));
 
@@ -0,0 +1,232 @@
+//! The connection supervisor. It watches the connection of a logged in
+//! client, reconnects with exponential backoff when the connection drops,
+//! and publishes [`AppEvent::LoggedOut`] when the session can not be
+//! recovered. Changes of the [`ConnectionState`] are published as
+//! [`AppEvent::ConnectionChanged`].
+
+use std::{io, sync::Arc, sync::Mutex, time::Duration};
+
+use ricq::{
+    client::{Connector, DefaultConnector, NetworkStatus, Token},
+    ext::common::after_login,
+    Client, LoginResponse,
+};
+use tokio::{
+    sync::broadcast::Receiver,
+    task,
+    task::JoinHandle,
+    time::{sleep, timeout},
+};
+
+use crate::db::account::restore_status;
+use crate::event::{publish, subscribe, AppEvent};
+
+const INITIAL_DELAY: Duration = Duration::from_secs(1);
+const MAX_DELAY: Duration = Duration::from_secs(60);
+/// A connection that does not answer a heartbeat in time is considered lost.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The task running `client.start`, which ends when the connection is lost.
+static CONNECTION: Mutex<Option<JoinHandle<()>>> = Mutex::new(None);
+
+static STATE: Mutex<(ConnectionState, Option<String>)> =
+    Mutex::new((ConnectionState::Connecting, None));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    Connecting,
+    Online,
+    /// Waiting for the next attempt to reconnect.
+    Reconnecting,
+    Offline,
+}
+
+impl ConnectionState {
+    pub(crate) fn label(&self) -> &'static str {
+        match self {
+            ConnectionState::Connecting => "Connecting…",
+            ConnectionState::Online => "Online",
+            ConnectionState::Reconnecting => "Reconnecting…",
+            ConnectionState::Offline => "Offline",
+        }
+    }
+
+    pub(crate) fn icon_name(&self) -> &'static str {
+        match self {
+            ConnectionState::Online => "network-transmit-receive-symbolic",
+            ConnectionState::Connecting | ConnectionState::Reconnecting => "network-idle-symbolic",
+            ConnectionState::Offline => "network-offline-symbolic",
+        }
+    }
+}
+
+/// The current state, and the last error if there is one.
+pub(crate) fn connection_state() -> (ConnectionState, Option<String>) {
+    STATE.lock().unwrap().clone()
+}
+
+fn set_state(state: ConnectionState, error: Option<String>) {
+    *STATE.lock().unwrap() = (state, error.clone());
+    publish(AppEvent::ConnectionChanged { state, error });
+}
+
+enum Offline {
+    /// The connection was closed by the network or by the server.
+    Dropped(String),
+    /// Another device logged in with the same account.
+    Kicked(String),
+}
+
+enum ReconnectError {
+    /// Worth another try, such as a network error.
+    Retry(String),
+    /// The saved token is rejected, so retrying will not help.
+    Fatal(String),
+}
+
+/// Connect `client` to the fastest server and start handling its packets.
+pub(crate) async fn connect(client: &Arc<Client>) -> io::Result<()> {
+    let stream = DefaultConnector.connect(client).await?;
+    let handle = task::spawn({
+        let client = client.clone();
+        async move { client.start(stream).await }
+    });
+    task::yield_now().await;
+
+    if let Some(previous) = CONNECTION.lock().unwrap().replace(handle) {
+        previous.abort();
+    }
+    Ok(())
+}
+
+/// Keep the logged in `client` online until the user gets logged out.
+pub(crate) fn supervise(client: Arc<Client>) {
+    task::spawn(async move {
+        let mut events = subscribe();
+        let mut token = client.gen_token().await;
+        set_state(ConnectionState::Online, None);
+        loop {
+            let connection = CONNECTION.lock().unwrap().take();
+            let offline = tokio::select! {
+                _ = wait_for_connection(connection) => {
+                    Offline::Dropped("Connection closed".to_string())
+                }
+                err = wait_for_heartbeat_failure(&client) => Offline::Dropped(err),
+                offline = wait_for_offline(&mut events) => offline,
+            };
+            client.stop(NetworkStatus::Stop);
+
+            let result = match offline {
+                Offline::Dropped(err) => {
+                    log::warn!("Connection lost, reconnecting: {}", err);
+                    set_state(ConnectionState::Reconnecting, Some(err));
+                    reconnect(&client, &token).await
+                }
+                Offline::Kicked(reason) => Err(reason),
+            };
+            match result {
+                Ok(()) => {
+                    token = client.gen_token().await;
+                    set_state(ConnectionState::Online, None);
+                }
+                Err(reason) => {
+                    set_state(ConnectionState::Offline, Some(reason.clone()));
+                    // Leave the client connected, so the login page can use it.
+                    reconnect_without_login(&client).await;
+                    publish(AppEvent::LoggedOut { reason });
+                    break;
+                }
+            }
+        }
+    });
+}
+
+async fn wait_for_connection(connection: Option<JoinHandle<()>>) {
+    if let Some(connection) = connection {
+        if let Err(err) = connection.await {
+            log::warn!("Connection task failed: {}", err);
+        }
+    }
+}
+
+async fn wait_for_heartbeat_failure(client: &Client) -> String {
+    loop {
+        sleep(HEARTBEAT_INTERVAL).await;
+        match timeout(HEARTBEAT_TIMEOUT, client.heartbeat()).await {
+            Ok(Ok(())) => {}
+            Ok(Err(err)) => return format!("Heartbeat failed: {}", err),
+            Err(_) => return "Heartbeat timed out".to_string(),
+        }
+    }
+}
+
+async fn wait_for_offline(events: &mut Receiver<AppEvent>) -> Offline {
+    loop {
+        match events.recv().await {
+            Ok(AppEvent::MSFOffline { title, info }) => {
+                log::warn!("MSF offline: {}: {}", title, info);
+                return Offline::Dropped(format!("{}: {}", title, info));
+            }
+            Ok(AppEvent::KickedOffline { title, tips }) => {
+                log::warn!("Kicked offline: {}: {}", title, tips);
+                return Offline::Kicked(format!("{}: {}", title, tips));
+            }
+            Ok(_) | Err(_) => {}
+        }
+    }
+}
+
+/// Reconnect and log in with `token`, backing off exponentially until it
+/// succeeds or the token gets rejected.
+async fn reconnect(client: &Arc<Client>, token: &Token) -> Result<(), String> {
+    let mut delay = INITIAL_DELAY;
+    loop {
+        sleep(delay).await;
+        set_state(ConnectionState::Connecting, connection_state().1);
+        match try_reconnect(client, token).await {
+            Ok(()) => {
+                log::info!("Reconnected");
+                return Ok(());
+            }
+            Err(ReconnectError::Retry(err)) => {
+                log::warn!("Failed to reconnect, retrying in {:?}: {}", delay, err);
+                set_state(ConnectionState::Reconnecting, Some(err));
+                delay = (delay * 2).min(MAX_DELAY);
+            }
+            Err(ReconnectError::Fatal(err)) => return Err(err),
+        }
+    }
+}
+
+async fn try_reconnect(client: &Arc<Client>, token: &Token) -> Result<(), ReconnectError> {
+    connect(client)
+        .await
+        .map_err(|err| ReconnectError::Retry(err.to_string()))?;
+
+    let result = match client.token_login(token.clone()).await {
+        Ok(LoginResponse::Success(_)) => Ok(()),
+        Ok(resp) => Err(ReconnectError::Fatal(format!(
+            "Session expired, please log in again ({:?})",
+            resp
+        ))),
+        Err(err) => Err(ReconnectError::Retry(err.to_string())),
+    };
+    if result.is_err() {
+        client.stop(NetworkStatus::Stop);
+        return result;
+    }
+
+    after_login(client).await;
+    restore_status(client).await;
+    Ok(())
+}
+
+async fn reconnect_without_login(client: &Arc<Client>) {
+    let mut delay = INITIAL_DELAY;
+    while let Err(err) = connect(client).await {
+        log::warn!("Failed to reconnect, retrying in {:?}: {}", delay, err);
+        sleep(delay).await;
+        delay = (delay * 2).min(MAX_DELAY);
+    }
+}
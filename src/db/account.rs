@@ -0,0 +1,106 @@
+//! Our own online status and profile. The chosen status is kept in the
+//! configs table, since the server forgets it on every login.
+
+use std::error::Error;
+
+use ricq::Client;
+
+use crate::handler::CLIENT;
+
+use super::sql::{load_sql_config, save_sql_config};
+
+type Result<T> = std::result::Result<T, Box<dyn Error + Send + Sync>>;
+
+const STATUS_KEY: &str = "online_status";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnlineStatus {
+    Online,
+    Away,
+    Busy,
+    Invisible,
+}
+
+impl OnlineStatus {
+    pub const ALL: [OnlineStatus; 4] = [
+        OnlineStatus::Online,
+        OnlineStatus::Away,
+        OnlineStatus::Busy,
+        OnlineStatus::Invisible,
+    ];
+
+    /// The status code of the protocol.
+    fn code(&self) -> i32 {
+        match self {
+            OnlineStatus::Online => 11,
+            OnlineStatus::Away => 31,
+            OnlineStatus::Invisible => 41,
+            OnlineStatus::Busy => 50,
+        }
+    }
+
+    pub(crate) fn label(&self) -> &'static str {
+        match self {
+            OnlineStatus::Online => "Online",
+            OnlineStatus::Away => "Away",
+            OnlineStatus::Busy => "Busy",
+            OnlineStatus::Invisible => "Invisible",
+        }
+    }
+
+    pub(crate) fn icon_name(&self) -> &'static str {
+        match self {
+            OnlineStatus::Online => "user-available-symbolic",
+            OnlineStatus::Away => "user-away-symbolic",
+            OnlineStatus::Busy => "user-busy-symbolic",
+            OnlineStatus::Invisible => "user-invisible-symbolic",
+        }
+    }
+
+    fn from_code(code: i32) -> Option<Self> {
+        OnlineStatus::ALL
+            .into_iter()
+            .find(|status| status.code() == code)
+    }
+}
+
+/// The status chosen last time, or `Online`.
+pub(crate) fn saved_status() -> OnlineStatus {
+    load_sql_config(STATUS_KEY)
+        .ok()
+        .flatten()
+        .and_then(|code| code.parse().ok())
+        .and_then(OnlineStatus::from_code)
+        .unwrap_or(OnlineStatus::Online)
+}
+
+pub(crate) async fn set_status(status: OnlineStatus) -> Result<()> {
+    let client = CLIENT.get().unwrap();
+    client.update_online_status(status.code()).await?;
+    save_sql_config(STATUS_KEY, status.code().to_string())?;
+    Ok(())
+}
+
+/// Apply the saved status after logging in or reconnecting. Being online is
+/// the default of a new session, so nothing is sent for it.
+pub(crate) async fn restore_status(client: &Client) {
+    let status = saved_status();
+    if status == OnlineStatus::Online {
+        return;
+    }
+    if let Err(err) = client.update_online_status(status.code()).await {
+        log::warn!("Failed to restore the online status {:?}: {}", status, err);
+    }
+}
+
+pub(crate) async fn set_nickname(nickname: String) -> Result<()> {
+    let client = CLIENT.get().unwrap();
+    client.update_nickname(nickname).await?;
+    Ok(())
+}
+
+pub(crate) async fn set_signature(signature: String) -> Result<()> {
+    let client = CLIENT.get().unwrap();
+    client.update_signature(signature).await?;
+    Ok(())
+}
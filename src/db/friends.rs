@@ -0,0 +1,86 @@
+//! Friend management: each operation is sent to the server first, and only
+//! applied to the local tables once it succeeds.
+
+use std::error::Error;
+
+use crate::event::{publish, AppEvent};
+use crate::handler::CLIENT;
+
+use super::sql::{
+    delete_friends_group, refresh_friends_list, rename_friends_group, set_friend_category,
+    set_friend_remark,
+};
+
+type Result<T> = std::result::Result<T, Box<dyn Error + Send + Sync>>;
+
+fn friends_updated() {
+    publish(AppEvent::ContactsUpdated {
+        friends: true,
+        groups: false,
+    });
+}
+
+pub(crate) async fn edit_remark(friend_id: i64, remark: String) -> Result<()> {
+    let client = CLIENT.get().unwrap();
+    client
+        .modify_friend_remark(friend_id, remark.clone())
+        .await?;
+    set_friend_remark(friend_id, &remark)?;
+    friends_updated();
+    publish(AppEvent::FriendRemarkChanged { friend_id, remark });
+    Ok(())
+}
+
+pub(crate) async fn move_friend(friend_id: i64, category_id: u8) -> Result<()> {
+    let client = CLIENT.get().unwrap();
+    client.move_friend_group(friend_id, category_id).await?;
+    set_friend_category(friend_id, category_id)?;
+    friends_updated();
+    Ok(())
+}
+
+/// The contact sync removes the friend from the table on `DeleteFriend`.
+pub(crate) async fn delete_friend(friend_id: i64) -> Result<()> {
+    let client = CLIENT.get().unwrap();
+    client.delete_friend(friend_id).await?;
+    publish(AppEvent::DeleteFriend { friend_id });
+    Ok(())
+}
+
+/// Ask `user_id` to become our friend. Whether they accept is only known
+/// when `NewFriend` arrives; a rejection is never told.
+pub(crate) async fn request_friend(user_id: i64, message: String) -> Result<()> {
+    let client = CLIENT.get().unwrap();
+    client.add_friend(user_id, message).await?;
+    Ok(())
+}
+
+/// The server assigns the id of the new category, so the whole list is
+/// fetched again to learn it.
+pub(crate) async fn create_category(name: String) -> Result<()> {
+    let client = CLIENT.get().unwrap();
+    client.add_friend_group(0, name).await?;
+    refresh_friends_list()
+        .await
+        .map_err(|err| err.to_string())?;
+    friends_updated();
+    Ok(())
+}
+
+pub(crate) async fn rename_category(category_id: u8, name: String) -> Result<()> {
+    let client = CLIENT.get().unwrap();
+    client
+        .rename_friend_group(category_id, name.clone())
+        .await?;
+    rename_friends_group(category_id, &name)?;
+    friends_updated();
+    Ok(())
+}
+
+pub(crate) async fn delete_category(category_id: u8) -> Result<()> {
+    let client = CLIENT.get().unwrap();
+    client.delete_friend_group(category_id).await?;
+    delete_friends_group(category_id)?;
+    friends_updated();
+    Ok(())
+}
@@ -1,2 +1,7 @@
+pub mod account;
+pub mod friends;
 pub mod fs;
 pub mod sql;
+mod sync;
+
+pub(crate) use sync::start_contact_sync;
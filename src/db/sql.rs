@@ -1,9 +1,12 @@
+use std::collections::HashSet;
 use std::error::Error;
 
 use crate::config::DB_VERSION;
 use crate::handler::CLIENT;
 use resource_loader::{SqlDataBase, SyncCreatePath, SyncLoadResource};
-use ricq::structs::{FriendGroupInfo, FriendInfo, GroupInfo};
+use ricq::structs::{
+    FriendGroupInfo, FriendInfo, GroupInfo, GroupMemberInfo, GroupMemberPermission,
+};
 use rusqlite::{params, Connection};
 
 pub struct SqlDb;
@@ -45,12 +48,85 @@ pub struct FriendsGroup {
     pub online_friends: i32,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Group {
     pub id: i64,
     pub name: String,
 }
 
+#[derive(Debug, Clone)]
+pub struct GroupMember {
+    pub group_id: i64,
+    pub uin: i64,
+    pub nickname: String,
+    /// The name set in the group, which may be empty.
+    pub card_name: String,
+    pub permission: GroupMemberPermission,
+    pub special_title: String,
+    /// In seconds since the epoch.
+    pub last_speak_time: i64,
+}
+
+impl GroupMember {
+    /// The card name, or the nickname if there is no card name.
+    pub fn display_name(&self) -> &str {
+        if self.card_name.is_empty() {
+            &self.nickname
+        } else {
+            &self.card_name
+        }
+    }
+}
+
+fn permission_to_int(permission: &GroupMemberPermission) -> u8 {
+    match permission {
+        GroupMemberPermission::Owner => 0,
+        GroupMemberPermission::Administrator => 1,
+        GroupMemberPermission::Member => 2,
+    }
+}
+
+fn permission_from_int(permission: u8) -> GroupMemberPermission {
+    match permission {
+        0 => GroupMemberPermission::Owner,
+        1 => GroupMemberPermission::Administrator,
+        _ => GroupMemberPermission::Member,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChatKind {
+    Friend,
+    Group,
+    /// A private chat with a stranger through a group both are in. Its
+    /// history is kept apart from the friend chat and from the temp chats
+    /// through other groups.
+    Temp {
+        group_id: i64,
+    },
+}
+
+impl ChatKind {
+    pub fn is_group(&self) -> bool {
+        *self == ChatKind::Group
+    }
+}
+
+/// A message in the local history, with its elements encoded by
+/// `utils::message::encode_elements`.
+#[derive(Debug, Clone)]
+pub struct MessageRecord {
+    pub chat_id: i64,
+    pub kind: ChatKind,
+    pub seq: i32,
+    pub time: i32,
+    pub sender_id: i64,
+    /// The group card of the sender in group chats, which may be empty, as
+    /// the name is resolved when the message is loaded.
+    pub sender_name: String,
+    pub elements: Vec<u8>,
+}
+
 pub fn init_sqlite() {
     let conn = SqlDb::load_resource(()).expect("Load Sqlite Db Failure");
 
@@ -88,16 +164,100 @@ pub fn init_sqlite() {
 
     conn.execute(
         "Create table if not exists groups (
+            id              INT PRIMARY KEY,
+            name            TEXT NOT NULL,
+            member_count    INT NOT NULL DEFAULT 0,
+            muted           INT NOT NULL DEFAULT 0
+        )",
+        [],
+    )
+    .unwrap();
+
+    conn.execute(
+        "Create table if not exists left_groups (
             id          INT PRIMARY KEY,
-            name        TEXT NOT NULL
+            kind        INT NOT NULL
+        )",
+        [],
+    )
+    .unwrap();
+
+    conn.execute(
+        "Create table if not exists group_members (
+            group_id        INT NOT NULL,
+            uin             INT NOT NULL,
+            nickname        TEXT NOT NULL,
+            card_name       TEXT NOT NULL,
+            permission      INT NOT NULL,
+            special_title   TEXT NOT NULL,
+            last_speak_time INT NOT NULL,
+            PRIMARY KEY (group_id, uin)
+        )",
+        [],
+    )
+    .unwrap();
+
+    // `kind` is 0 for friends, 1 for groups and 2 for temp chats, whose
+    // group is `group_id`. It is 0 otherwise.
+    conn.execute(
+        "Create table if not exists messages (
+            chat_id     INT NOT NULL,
+            kind        INT NOT NULL,
+            group_id    INT NOT NULL,
+            seq         INT NOT NULL,
+            time        INT NOT NULL,
+            sender_id   INT NOT NULL,
+            sender_name TEXT NOT NULL,
+            elements    BLOB NOT NULL,
+            PRIMARY KEY (chat_id, kind, group_id, seq)
+        )",
+        [],
+    )
+    .unwrap();
+
+    // The messages which are not sent yet, keyed like the messages table.
+    conn.execute(
+        "Create table if not exists pending_messages (
+            id          INTEGER PRIMARY KEY,
+            chat_id     INT NOT NULL,
+            kind        INT NOT NULL,
+            group_id    INT NOT NULL,
+            content     TEXT NOT NULL
         )",
         [],
     )
     .unwrap();
 }
 
+/// The `(kind, group_id)` of the chat in the messages table.
+fn history_key(kind: ChatKind) -> (i32, i64) {
+    match kind {
+        ChatKind::Friend => (0, 0),
+        ChatKind::Group => (1, 0),
+        ChatKind::Temp { group_id } => (2, group_id),
+    }
+}
+
+/// Delete the rows of `table` whose id is not in `ids`.
+fn delete_missing(conn: &Connection, table: &str, ids: &HashSet<i64>) -> rusqlite::Result<()> {
+    let existing = conn
+        .prepare(&format!("Select id from {}", table))?
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<i64>>>()?;
+    let mut stmt = conn.prepare(&format!("DELETE FROM {} WHERE id=?1", table))?;
+    for id in existing {
+        if !ids.contains(&id) {
+            stmt.execute([id])?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Sync the friends and their groups with the server, updating the changed
+/// rows and deleting the ones which are gone.
 pub async fn refresh_friends_list() -> Result<(), Box<dyn Error>> {
-    let conn = get_db();
+    let mut conn = get_db();
     // Request for friend list
     let client = CLIENT.get().unwrap();
     let res = client.get_friend_list().await?;
@@ -116,9 +276,12 @@ pub async fn refresh_friends_list() -> Result<(), Box<dyn Error>> {
             id: friends_group.group_id,
             name: friends_group.group_name,
             online_friends: friends_group.online_friend_count,
-        });
-    conn.execute("DELETE FROM friends_groups", [])?;
-    let mut stmt = conn.prepare("INSERT INTO friends_groups values (?1, ?2, ?3)")?;
+        })
+        .collect::<Vec<_>>();
+    let tx = conn.transaction()?;
+    let ids = friends_groups.iter().map(|group| group.id as i64).collect();
+    delete_missing(&tx, "friends_groups", &ids)?;
+    let mut stmt = tx.prepare("REPLACE INTO friends_groups values (?1, ?2, ?3)")?;
     for friends_group in friends_groups {
         stmt.execute(params![
             friends_group.id,
@@ -126,53 +289,564 @@ pub async fn refresh_friends_list() -> Result<(), Box<dyn Error>> {
             friends_group.online_friends
         ])?;
     }
+    drop(stmt);
     // Handle the friends
-    let friends = friends.into_iter().map(
-        |FriendInfo {
-             uin,
-             nick,
-             remark,
-             group_id,
-             ..
-         }| Friend {
-            id: uin,
-            name: nick,
-            remark,
-            group_id,
-        },
-    );
-    conn.execute("DELETE FROM friends", [])?;
-    let mut stmt = conn.prepare("INSERT INTO friends values (?1, ?2, ?3, ?4)")?;
+    // TODO: Store the status and the client type of each friend, to show who
+    // is online and sort them first. The friend list packet carries them, but
+    // `get_friend_list` drops them while decoding `FriendInfo`.
+    let friends = friends
+        .into_iter()
+        .map(
+            |FriendInfo {
+                 uin,
+                 nick,
+                 remark,
+                 group_id,
+                 ..
+             }| Friend {
+                id: uin,
+                name: nick,
+                remark,
+                group_id,
+            },
+        )
+        .collect::<Vec<_>>();
+    let ids = friends.iter().map(|friend| friend.id).collect();
+    delete_missing(&tx, "friends", &ids)?;
     for friend in friends {
-        stmt.execute(params![
-            friend.id,
-            friend.name,
-            friend.remark,
-            friend.group_id
-        ])?;
+        upsert_friend(&tx, &friend)?;
     }
+    tx.commit()?;
 
     Ok(())
 }
 
+/// Sync the groups with the server, like `refresh_friends_list`.
 pub async fn refresh_groups_list() -> Result<(), Box<dyn Error>> {
-    let conn = get_db();
+    let mut conn = get_db();
     let client = CLIENT.get().unwrap();
     let res = client.get_group_list().await?;
 
     let groups = res
         .into_iter()
-        .map(|GroupInfo { code, name, .. }| Group { id: code, name });
+        .map(
+            |GroupInfo {
+                 code,
+                 name,
+                 member_count,
+                 ..
+             }| (Group { id: code, name }, member_count),
+        )
+        .collect::<Vec<_>>();
+
+    let tx = conn.transaction()?;
+    let ids: HashSet<i64> = groups.iter().map(|(group, _)| group.id).collect();
+    let missing = tx
+        .prepare("Select id from groups")?
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<i64>>>()?
+        .into_iter()
+        .filter(|id| !ids.contains(id));
+    // We were not told why, so they are taken as left.
+    for group_id in missing {
+        remove_group(&tx, group_id, GroupLeft::Left)?;
+    }
+    for (group, member_count) in groups {
+        upsert_group(&tx, &group)?;
+        set_group_member_count(&tx, group.id, member_count as i64)?;
+    }
+    tx.commit()?;
+
+    Ok(())
+}
+
+pub fn upsert_friend(conn: &Connection, friend: &Friend) -> rusqlite::Result<()> {
+    conn.execute(
+        "REPLACE INTO friends values (?1, ?2, ?3, ?4)",
+        params![friend.id, friend.name, friend.remark, friend.group_id],
+    )
+    .map(|_| ())
+}
+
+pub fn delete_friend(friend_id: i64) -> rusqlite::Result<()> {
+    get_db()
+        .execute("DELETE FROM friends WHERE id=?1", [friend_id])
+        .map(|_| ())
+}
+
+pub fn get_friend(friend_id: i64) -> Option<Friend> {
+    get_db()
+        .query_row(
+            "Select id, name, remark, group_id from friends where id=?1",
+            [friend_id],
+            |row| {
+                Ok(Friend {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    remark: row.get(2)?,
+                    group_id: row.get(3)?,
+                })
+            },
+        )
+        .ok()
+}
+
+pub fn set_friend_remark(friend_id: i64, remark: &str) -> rusqlite::Result<()> {
+    get_db()
+        .execute(
+            "UPDATE friends SET remark=?2 WHERE id=?1",
+            params![friend_id, remark],
+        )
+        .map(|_| ())
+}
+
+pub fn set_friend_category(friend_id: i64, category_id: u8) -> rusqlite::Result<()> {
+    get_db()
+        .execute(
+            "UPDATE friends SET group_id=?2 WHERE id=?1",
+            params![friend_id, category_id],
+        )
+        .map(|_| ())
+}
+
+pub fn get_friends_groups() -> rusqlite::Result<Vec<FriendsGroup>> {
+    let conn = get_db();
+    let mut stmt = conn.prepare("Select id, name, online_friends from friends_groups")?;
+    let friends_groups = stmt
+        .query_map([], |row| {
+            Ok(FriendsGroup {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                online_friends: row.get(2)?,
+            })
+        })?
+        .collect();
+
+    friends_groups
+}
+
+pub fn rename_friends_group(id: u8, name: &str) -> rusqlite::Result<()> {
+    get_db()
+        .execute(
+            "UPDATE friends_groups SET name=?2 WHERE id=?1",
+            params![id, name],
+        )
+        .map(|_| ())
+}
+
+/// Delete the friend group, whose friends are moved to the default one, as
+/// the server does.
+pub fn delete_friends_group(id: u8) -> rusqlite::Result<()> {
+    let mut conn = get_db();
+    let tx = conn.transaction()?;
+    tx.execute("UPDATE friends SET group_id=0 WHERE group_id=?1", [id])?;
+    tx.execute("DELETE FROM friends_groups WHERE id=?1", [id])?;
+    tx.commit()
+}
+
+/// Insert the group, or rename it if it exists, keeping its member count.
+pub fn upsert_group(conn: &Connection, group: &Group) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO groups (id, name) values (?1, ?2)
+            ON CONFLICT(id) DO UPDATE SET name=excluded.name",
+        params![group.id, group.name],
+    )?;
+    conn.execute("DELETE FROM left_groups WHERE id=?1", [group.id])
+        .map(|_| ())
+}
+
+/// Why a group is no longer joined, as kept in `left_groups`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupLeft {
+    Disbanded,
+    Left,
+}
 
-    conn.execute("DELETE FROM groups", [])?;
-    let mut stmt = conn.prepare("INSERT INTO groups values (?1, ?2)")?;
-    for group in groups {
-        stmt.execute(params![group.id, group.name])?;
+impl GroupLeft {
+    fn code(self) -> i32 {
+        match self {
+            GroupLeft::Disbanded => 0,
+            GroupLeft::Left => 1,
+        }
     }
 
+    fn from_code(code: i32) -> Option<Self> {
+        match code {
+            0 => Some(GroupLeft::Disbanded),
+            1 => Some(GroupLeft::Left),
+            _ => None,
+        }
+    }
+}
+
+/// Why we can no longer send messages to the group, if it is disbanded or
+/// left.
+pub fn get_group_left(group_id: i64) -> Option<GroupLeft> {
+    get_db()
+        .query_row(
+            "Select kind from left_groups where id=?1",
+            [group_id],
+            |row| row.get(0),
+        )
+        .ok()
+        .and_then(GroupLeft::from_code)
+}
+
+pub fn set_group_member_count(
+    conn: &Connection,
+    group_id: i64,
+    member_count: i64,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE groups SET member_count=?2 WHERE id=?1",
+        params![group_id, member_count],
+    )
+    .map(|_| ())
+}
+
+/// `None` if the group is unknown, or its members have never been counted.
+pub fn get_group_member_count(group_id: i64) -> Option<i64> {
+    get_db()
+        .query_row(
+            "Select member_count from groups where id=?1",
+            [group_id],
+            |row| row.get(0),
+        )
+        .ok()
+        .filter(|count| *count > 0)
+}
+
+/// Whether the notifications of the group are muted.
+pub fn is_group_muted(group_id: i64) -> bool {
+    get_db()
+        .query_row("Select muted from groups where id=?1", [group_id], |row| {
+            row.get(0)
+        })
+        .unwrap_or(false)
+}
+
+pub fn set_group_muted(group_id: i64, muted: bool) -> rusqlite::Result<()> {
+    get_db()
+        .execute(
+            "UPDATE groups SET muted=?2 WHERE id=?1",
+            params![group_id, muted],
+        )
+        .map(|_| ())
+}
+
+/// Replace the cached members of the group with the ones on the server.
+pub async fn refresh_group_members(group_id: i64) -> Result<(), Box<dyn Error>> {
+    let client = CLIENT.get().unwrap();
+    let info = client
+        .get_group_info(group_id)
+        .await?
+        .ok_or("The group does not exist")?;
+    let members = client
+        .get_group_member_list(group_id, info.owner_uin)
+        .await?;
+
+    let mut conn = get_db();
+    let tx = conn.transaction()?;
+    tx.execute("DELETE FROM group_members WHERE group_id=?1", [group_id])?;
+    let mut stmt = tx.prepare("INSERT INTO group_members values (?1, ?2, ?3, ?4, ?5, ?6, ?7)")?;
+    for GroupMemberInfo {
+        uin,
+        nickname,
+        card_name,
+        permission,
+        special_title,
+        last_speak_time,
+        ..
+    } in members.iter()
+    {
+        stmt.execute(params![
+            group_id,
+            uin,
+            nickname,
+            card_name,
+            permission_to_int(permission),
+            special_title,
+            last_speak_time
+        ])?;
+    }
+    drop(stmt);
+    set_group_member_count(&tx, group_id, members.len() as i64)?;
+    tx.commit()?;
+
     Ok(())
 }
 
+/// The name to show for a group member: the group card, the friend remark,
+/// the nickname or the uin, whichever is found first. `group_card` is the
+/// card seen in a message, for members who are not cached yet.
+pub fn get_member_name(group_id: i64, uin: i64, group_card: &str) -> String {
+    let (card_name, nickname) = get_db()
+        .query_row(
+            "Select card_name, nickname from group_members where group_id=?1 and uin=?2",
+            [group_id, uin],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+        )
+        .unwrap_or_default();
+    let non_empty = |name: String| Some(name).filter(|name| !name.is_empty());
+
+    non_empty(card_name)
+        .or_else(|| non_empty(group_card.to_string()))
+        .or_else(|| find_friend_remark(uin).and_then(non_empty))
+        .or_else(|| non_empty(nickname))
+        .unwrap_or_else(|| uin.to_string())
+}
+
+/// Update the cached card of a member, returning whether it has changed.
+pub fn update_member_card(group_id: i64, uin: i64, card_name: &str) -> rusqlite::Result<bool> {
+    get_db()
+        .execute(
+            "UPDATE group_members SET card_name=?3
+                WHERE group_id=?1 and uin=?2 and card_name<>?3",
+            params![group_id, uin, card_name],
+        )
+        .map(|changed| changed > 0)
+}
+
+/// Whether the members of the group have been cached.
+pub fn has_group_members(group_id: i64) -> bool {
+    get_db()
+        .query_row(
+            "Select exists(Select 1 from group_members where group_id=?1)",
+            [group_id],
+            |row| row.get(0),
+        )
+        .unwrap_or(false)
+}
+
+/// The cached members of the group, the owner and the admins first.
+pub fn get_group_members(group_id: i64) -> rusqlite::Result<Vec<GroupMember>> {
+    let conn = get_db();
+    let mut stmt = conn.prepare(
+        "Select uin, nickname, card_name, permission, special_title, last_speak_time
+            from group_members where group_id=?1
+            order by permission, last_speak_time desc",
+    )?;
+    let members = stmt
+        .query_map([group_id], |row| {
+            Ok(GroupMember {
+                group_id,
+                uin: row.get(0)?,
+                nickname: row.get(1)?,
+                card_name: row.get(2)?,
+                permission: permission_from_int(row.get(3)?),
+                special_title: row.get(4)?,
+                last_speak_time: row.get(5)?,
+            })
+        })?
+        .collect();
+
+    members
+}
+
+fn remove_group(conn: &Connection, group_id: i64, left: GroupLeft) -> rusqlite::Result<()> {
+    conn.execute("DELETE FROM group_members WHERE group_id=?1", [group_id])?;
+    conn.execute("DELETE FROM groups WHERE id=?1", [group_id])?;
+    conn.execute(
+        "REPLACE INTO left_groups values (?1, ?2)",
+        params![group_id, left.code()],
+    )
+    .map(|_| ())
+}
+
+/// Delete the group which is disbanded or left, and remember why so its
+/// chatroom stays read only.
+pub fn delete_group(group_id: i64, left: GroupLeft) -> rusqlite::Result<()> {
+    remove_group(&get_db(), group_id, left)
+}
+
+pub fn get_friends() -> rusqlite::Result<Vec<Friend>> {
+    let conn = get_db();
+    let mut stmt = conn.prepare("Select id, name, remark, group_id from friends")?;
+    let friends = stmt
+        .query_map([], |row| {
+            Ok(Friend {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                remark: row.get(2)?,
+                group_id: row.get(3)?,
+            })
+        })?
+        .collect();
+
+    friends
+}
+
+pub fn get_groups() -> rusqlite::Result<Vec<Group>> {
+    let conn = get_db();
+    let mut stmt = conn.prepare("Select id, name from groups order by name")?;
+    let groups = stmt
+        .query_map([], |row| {
+            Ok(Group {
+                id: row.get(0)?,
+                name: row.get(1)?,
+            })
+        })?
+        .collect();
+
+    groups
+}
+
+/// The groups which `uin` is known to be in. Only the groups whose members
+/// have been cached are taken into account.
+pub fn get_common_groups(uin: i64) -> rusqlite::Result<Vec<Group>> {
+    let conn = get_db();
+    let mut stmt = conn.prepare(
+        "Select groups.id, groups.name from groups
+            join group_members on group_members.group_id=groups.id
+            where group_members.uin=?1 order by groups.name",
+    )?;
+    let groups = stmt
+        .query_map([uin], |row| {
+            Ok(Group {
+                id: row.get(0)?,
+                name: row.get(1)?,
+            })
+        })?
+        .collect();
+
+    groups
+}
+
+/// Messages which are already in the history are ignored, since the same
+/// message may be both received and fetched from the server.
+pub fn save_message(record: &MessageRecord) -> rusqlite::Result<()> {
+    let (kind, group_id) = history_key(record.kind);
+    get_db()
+        .execute(
+            "INSERT OR IGNORE INTO messages values (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                record.chat_id,
+                kind,
+                group_id,
+                record.seq,
+                record.time,
+                record.sender_id,
+                record.sender_name,
+                record.elements
+            ],
+        )
+        .map(|_| ())
+}
+
+/// Queue a message to be sent to the chat, returning its id.
+pub fn add_pending_message(chat_id: i64, kind: ChatKind, content: &str) -> rusqlite::Result<i64> {
+    let (kind, group_id) = history_key(kind);
+    let conn = get_db();
+    conn.execute(
+        "INSERT INTO pending_messages (chat_id, kind, group_id, content) values (?1, ?2, ?3, ?4)",
+        params![chat_id, kind, group_id, content],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn delete_pending_message(id: i64) -> rusqlite::Result<()> {
+    get_db()
+        .execute("DELETE FROM pending_messages where id=?1", [id])
+        .map(|_| ())
+}
+
+/// The `(id, content)` of the messages queued for the chat, in the order
+/// they were queued.
+pub fn get_pending_messages(chat_id: i64, kind: ChatKind) -> rusqlite::Result<Vec<(i64, String)>> {
+    let (kind, group_id) = history_key(kind);
+    let conn = get_db();
+    let mut stmt = conn.prepare(
+        "Select id, content from pending_messages
+            where chat_id=?1 and kind=?2 and group_id=?3 order by id",
+    )?;
+    let pending = stmt
+        .query_map(params![chat_id, kind, group_id], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?
+        .collect();
+    pending
+}
+
+/// The rowid of the last message of the chat saved so far, or 0. Messages
+/// are never replaced, so those saved later have greater rowids.
+pub fn get_last_message_rowid(chat_id: i64, kind: ChatKind) -> rusqlite::Result<i64> {
+    let (kind, group_id) = history_key(kind);
+    get_db().query_row(
+        "Select ifnull(max(rowid), 0) from messages
+            where chat_id=?1 and kind=?2 and group_id=?3",
+        params![chat_id, kind, group_id],
+        |row| row.get(0),
+    )
+}
+
+/// Get at most `limit` messages of the chat which are older than `before`,
+/// a `(time, seq)` pair, in chronological order. Only the messages saved up
+/// to `max_rowid` are considered.
+pub fn get_messages_before(
+    chat_id: i64,
+    kind: ChatKind,
+    before: Option<(i32, i32)>,
+    max_rowid: i64,
+    limit: usize,
+) -> rusqlite::Result<Vec<MessageRecord>> {
+    let (time, seq) = before.unwrap_or((i32::MAX, i32::MAX));
+    let (kind_key, group_id) = history_key(kind);
+    let conn = get_db();
+    let mut stmt = conn.prepare(
+        "Select seq, time, sender_id, sender_name, elements from messages
+            where chat_id=?1 and kind=?2 and group_id=?3
+                and (time<?4 or (time=?4 and seq<?5)) and rowid<=?6
+            order by time desc, seq desc limit ?7",
+    )?;
+    let mut records = stmt
+        .query_map(
+            params![
+                chat_id,
+                kind_key,
+                group_id,
+                time,
+                seq,
+                max_rowid,
+                limit as i64
+            ],
+            |row| {
+                Ok(MessageRecord {
+                    chat_id,
+                    kind,
+                    seq: row.get(0)?,
+                    time: row.get(1)?,
+                    sender_id: row.get(2)?,
+                    sender_name: row.get(3)?,
+                    elements: row.get(4)?,
+                })
+            },
+        )?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    records.reverse();
+
+    Ok(records)
+}
+
+/// Like `get_friend_remark`, but quietly returns `None` for strangers.
+pub fn find_friend_remark(friend_id: i64) -> Option<String> {
+    get_db()
+        .query_row(
+            "Select remark from friends where id=?1",
+            [friend_id],
+            |row| row.get(0),
+        )
+        .ok()
+}
+
+pub fn rename_group(group_id: i64, name: &str) -> rusqlite::Result<()> {
+    get_db()
+        .execute(
+            "UPDATE groups SET name=?2 WHERE id=?1",
+            params![group_id, name],
+        )
+        .map(|_| ())
+}
+
 pub fn get_friend_remark(friend_id: i64) -> String {
     get_db()
         .query_row(
@@ -206,7 +880,7 @@ pub fn get_group_name(group_id: i64) -> String {
 }
 
 pub fn check_db_version() {
-    let conn = get_db();
+    let mut conn = get_db();
     let res = conn.query_row::<String, _, _>(
         "Select value from configs where key='db_version'",
         [],
@@ -215,9 +889,12 @@ pub fn check_db_version() {
     match res {
         Ok(version) => {
             let version: usize = version.parse().unwrap();
-            if version != DB_VERSION {
+            if version > DB_VERSION {
                 panic!("unrecognized database version")
             }
+            if version < DB_VERSION {
+                migrate_db(&mut conn, version).unwrap();
+            }
         }
         Err(err) => {
             if err.to_string() == "Query returned no rows" {
@@ -233,6 +910,26 @@ pub fn check_db_version() {
     }
 }
 
+/// Upgrade the tables created by the database `version` to `DB_VERSION`.
+fn migrate_db(conn: &mut Connection, version: usize) -> rusqlite::Result<()> {
+    let tx = conn.transaction()?;
+    if version < 2 {
+        tx.execute(
+            "ALTER TABLE groups ADD COLUMN member_count INT NOT NULL DEFAULT 0",
+            [],
+        )?;
+        tx.execute(
+            "ALTER TABLE groups ADD COLUMN muted INT NOT NULL DEFAULT 0",
+            [],
+        )?;
+    }
+    tx.execute(
+        "Update configs set value=?1 where key='db_version'",
+        [DB_VERSION.to_string()],
+    )?;
+    tx.commit()
+}
+
 pub fn load_sql_config(
     key: &(impl AsRef<str> + ?Sized),
 ) -> Result<Option<String>, rusqlite::Error> {
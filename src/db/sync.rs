@@ -0,0 +1,105 @@
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::connection::ConnectionState;
+use crate::event::{publish, subscribe, AppEvent};
+use crate::handler::{ACCOUNT, CLIENT};
+
+use super::sql::{
+    delete_friend, delete_group, get_db, refresh_friends_list, refresh_groups_list, rename_group,
+    upsert_friend, upsert_group, Friend, Group, GroupLeft,
+};
+
+/// Keep the friends and groups tables in sync: fully on every (re)connect,
+/// and incrementally from the events in between. Each change is announced
+/// with `AppEvent::ContactsUpdated`.
+pub(crate) fn start_contact_sync() {
+    let mut receiver = subscribe();
+    tokio::spawn(async move {
+        loop {
+            let event = match receiver.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            };
+            let is_self = |id: i64| Some(&id) == ACCOUNT.get();
+            let result = match event {
+                AppEvent::ConnectionChanged {
+                    state: ConnectionState::Online,
+                    ..
+                } => {
+                    sync_all().await;
+                    continue;
+                }
+                AppEvent::NewFriend {
+                    friend_id,
+                    nickname,
+                } => upsert_friend(
+                    &get_db(),
+                    &Friend {
+                        id: friend_id,
+                        name: nickname.clone(),
+                        remark: nickname,
+                        group_id: 0,
+                    },
+                )
+                .map(|_| (true, false)),
+                AppEvent::DeleteFriend { friend_id } => {
+                    delete_friend(friend_id).map(|_| (true, false))
+                }
+                AppEvent::GroupNameUpdate { group_id, name, .. } => {
+                    rename_group(group_id, &name).map(|_| (false, true))
+                }
+                AppEvent::GroupLeave {
+                    group_id,
+                    member_id,
+                    ..
+                } if is_self(member_id) => {
+                    delete_group(group_id, GroupLeft::Left).map(|_| (false, true))
+                }
+                AppEvent::GroupDisband { group_id, .. } => {
+                    delete_group(group_id, GroupLeft::Disbanded).map(|_| (false, true))
+                }
+                AppEvent::NewMember {
+                    group_id,
+                    member_id,
+                } if is_self(member_id) => add_group(group_id).await.map(|_| (false, true)),
+                _ => continue,
+            };
+            match result {
+                Ok((friends, groups)) => publish(AppEvent::ContactsUpdated { friends, groups }),
+                Err(err) => log::warn!("Failed to update the contacts: {}", err),
+            }
+        }
+    });
+}
+
+async fn add_group(group_id: i64) -> rusqlite::Result<()> {
+    let client = CLIENT.get().unwrap();
+    let name = match client.get_group_info(group_id).await {
+        Ok(Some(info)) => info.name,
+        Ok(None) => group_id.to_string(),
+        Err(err) => {
+            log::warn!("Failed to get the info of group {}: {}", group_id, err);
+            group_id.to_string()
+        }
+    };
+    upsert_group(&get_db(), &Group { id: group_id, name })
+}
+
+async fn sync_all() {
+    let friends = match refresh_friends_list().await {
+        Ok(()) => true,
+        Err(err) => {
+            log::warn!("Failed to sync the friends list: {}", err);
+            false
+        }
+    };
+    let groups = match refresh_groups_list().await {
+        Ok(()) => true,
+        Err(err) => {
+            log::warn!("Failed to sync the groups list: {}", err);
+            false
+        }
+    };
+    publish(AppEvent::ContactsUpdated { friends, groups });
+}
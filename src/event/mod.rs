@@ -0,0 +1,187 @@
+//! The application event layer. `AppHandler` converts every ricq event into
+//! an [`AppEvent`] and publishes it on a broadcast channel, which any
+//! component can subscribe to.
+
+mod notification;
+
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use relm4::Sender;
+use ricq::structs::GroupMemberPermission;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::connection::ConnectionState;
+use crate::utils::message::Message;
+
+pub(crate) use notification::start_notifications;
+
+/// How many events a slow subscriber may fall behind before it misses some.
+const CAPACITY: usize = 256;
+
+static EVENT_BUS: Lazy<broadcast::Sender<AppEvent>> = Lazy::new(|| broadcast::channel(CAPACITY).0);
+
+#[derive(Debug, Clone)]
+pub(crate) enum AppEvent {
+    GroupMessage {
+        group_id: i64,
+        message: Message,
+    },
+    /// Sent or received in a private chat with a friend.
+    FriendMessage {
+        friend_id: i64,
+        message: Message,
+    },
+    GroupTempMessage {
+        group_id: i64,
+        message: Message,
+    },
+    FriendMessageRecall {
+        friend_id: i64,
+        seq: i32,
+    },
+    GroupMessageRecall {
+        group_id: i64,
+        author_id: i64,
+        operator_id: i64,
+        seq: i32,
+    },
+    FriendPoke {
+        sender_id: i64,
+        receiver_id: i64,
+    },
+    NewFriend {
+        friend_id: i64,
+        nickname: String,
+    },
+    DeleteFriend {
+        friend_id: i64,
+    },
+    /// Changed by us, as ricq has no event for it.
+    FriendRemarkChanged {
+        friend_id: i64,
+        remark: String,
+    },
+    NewFriendRequest {
+        seq: i64,
+        requester_id: i64,
+        requester_nickname: String,
+        message: String,
+    },
+    /// Somebody wants to join a group we admin.
+    GroupRequest {
+        seq: i64,
+        time: i64,
+        group_id: i64,
+        group_name: String,
+        requester_id: i64,
+        requester_nickname: String,
+        message: String,
+    },
+    /// We are invited to a group.
+    SelfInvited {
+        seq: i64,
+        time: i64,
+        group_id: i64,
+        group_name: String,
+        invitor_id: i64,
+        invitor_nickname: String,
+    },
+    NewMember {
+        group_id: i64,
+        member_id: i64,
+    },
+    GroupLeave {
+        group_id: i64,
+        member_id: i64,
+        /// The one who kicked the member, if any.
+        operator_id: Option<i64>,
+    },
+    GroupMute {
+        group_id: i64,
+        operator_id: i64,
+        /// `0` when the whole group is muted.
+        target_id: i64,
+        /// Zero means unmuted.
+        duration: Duration,
+    },
+    GroupNameUpdate {
+        group_id: i64,
+        operator_id: i64,
+        name: String,
+    },
+    GroupDisband {
+        group_id: i64,
+        operator_id: i64,
+    },
+    MemberPermissionChange {
+        group_id: i64,
+        member_id: i64,
+        permission: GroupMemberPermission,
+    },
+    /// Seen in a message from the member, as ricq has no event for it.
+    MemberCardChanged {
+        group_id: i64,
+        member_id: i64,
+        name: String,
+    },
+    KickedOffline {
+        title: String,
+        tips: String,
+    },
+    MSFOffline {
+        title: String,
+        info: String,
+    },
+    ConnectionChanged {
+        state: ConnectionState,
+        /// The last error, such as why the connection was lost.
+        error: Option<String>,
+    },
+    /// The friends or groups tables have been changed.
+    ContactsUpdated {
+        friends: bool,
+        groups: bool,
+    },
+    /// The session can not be recovered, and the user has to log in again.
+    LoggedOut {
+        reason: String,
+    },
+}
+
+/// Publish `event` to all the current subscribers.
+pub(crate) fn publish(event: AppEvent) {
+    log::debug!("Publishing {:?}", event);
+    // An error only means that nobody is subscribing yet.
+    if let Err(broadcast::error::SendError(event)) = EVENT_BUS.send(event) {
+        log::debug!("No subscriber for {:?}", event);
+    }
+}
+
+pub(crate) fn subscribe() -> broadcast::Receiver<AppEvent> {
+    EVENT_BUS.subscribe()
+}
+
+/// Forward the events picked by `filter` to the input of a component.
+pub(crate) fn forward<T, F>(sender: Sender<T>, filter: F)
+where
+    T: Send + 'static,
+    F: Fn(AppEvent) -> Option<T> + Send + 'static,
+{
+    let mut receiver = subscribe();
+    tokio::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => {
+                    if let Some(msg) = filter(event) {
+                        sender.send(msg);
+                    }
+                }
+                Err(RecvError::Lagged(count)) => {
+                    log::warn!("A subscriber missed {} events", count);
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
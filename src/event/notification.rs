@@ -0,0 +1,72 @@
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::db::sql::{get_group_name, is_group_muted};
+use crate::handler::ACCOUNT;
+use crate::utils::message::Message;
+use crate::APP;
+
+use super::{subscribe, AppEvent};
+
+/// Send desktop notifications for the messages from the others, and for
+/// the requests and invitations.
+pub(crate) fn start_notifications() {
+    let mut receiver = subscribe();
+    tokio::spawn(async move {
+        loop {
+            let event = match receiver.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            };
+            let is_others = |message: &Message| Some(&message.sender_id) != ACCOUNT.get();
+            let app = APP.get().unwrap();
+            match event {
+                AppEvent::GroupMessage { group_id, message }
+                    if is_others(&message) && !is_group_muted(group_id) =>
+                {
+                    app.notify_group_message(group_id, &message.text());
+                }
+                AppEvent::FriendMessage { friend_id, message } if is_others(&message) => {
+                    app.notify_friend_message(friend_id, &message.text());
+                }
+                AppEvent::GroupTempMessage { group_id, message } => app.notify_request(
+                    message.sender_id,
+                    &format!("{} via {}", message.sender_name, get_group_name(group_id)),
+                    &message.text(),
+                ),
+                AppEvent::NewFriendRequest {
+                    requester_id,
+                    requester_nickname,
+                    message,
+                    ..
+                } => app.notify_request(
+                    requester_id,
+                    &format!("{} wants to add you as a friend", requester_nickname),
+                    &message,
+                ),
+                AppEvent::GroupRequest {
+                    group_name,
+                    requester_id,
+                    requester_nickname,
+                    message,
+                    ..
+                } => app.notify_request(
+                    requester_id,
+                    &format!("{} wants to join {}", requester_nickname, group_name),
+                    &message,
+                ),
+                AppEvent::SelfInvited {
+                    group_name,
+                    invitor_id,
+                    invitor_nickname,
+                    ..
+                } => app.notify_request(
+                    invitor_id,
+                    &format!("{} invited you to join {}", invitor_nickname, group_name),
+                    "",
+                ),
+                _ => {}
+            }
+        }
+    });
+}
@@ -64,6 +64,23 @@ impl SharedApplication {
 
         self.app.send_notification(None, &notification);
     }
+
+    pub fn notify_request(&self, requester_id: i64, title: &str, body: &str) {
+        let path = get_user_avatar_path(requester_id);
+
+        let notification = Notification::new(title);
+        notification.set_body(Some(body));
+
+        if path.exists() {
+            if let Ok(icon) = Pixbuf::from_file(path) {
+                notification.set_icon(&icon);
+            }
+        } else {
+            task::spawn(download_user_avatar_file(requester_id));
+        }
+
+        self.app.send_notification(None, &notification);
+    }
 }
 
 pub static APP: OnceCell<SharedApplication> = OnceCell::new();
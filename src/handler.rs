@@ -4,140 +4,348 @@ use async_trait::async_trait;
 use once_cell::sync::OnceCell;
 use ricq::client::event::*;
 use ricq::handler::{Handler, QEvent::*};
+use ricq::msg::MessageChain;
+use ricq::pb::msg::Ptt;
+use ricq::structs::MessageReceipt;
 use ricq::Client;
 
-use crate::app::main::{MainMsg, MAIN_SENDER};
-use crate::db::sql::get_friend_remark;
-use crate::utils::message::{get_contents_from, get_text_from, Message};
-use crate::APP;
+use crate::db::sql::{
+    get_friend_remark, get_member_name, save_message, update_member_card, ChatKind, MessageRecord,
+};
+use crate::event::{publish, AppEvent};
+use crate::utils::message::{
+    encode_elements, get_contents_from, resolve_video_urls, Content, Message,
+};
 
 pub struct AppHandler;
 
 pub static CLIENT: OnceCell<Arc<Client>> = OnceCell::new();
 pub static ACCOUNT: OnceCell<i64> = OnceCell::new();
 
+/// Save the message to the local history before it is shown, so that a
+/// chatroom opened by it will find the message in the history.
+pub(crate) fn save_to_history(record: MessageRecord) {
+    if let Err(err) = save_message(&record) {
+        log::warn!("Failed to save message to history: {}", err);
+    }
+}
+
+/// Resolve the name of the sender of a group message, and let the chatrooms
+/// know if the sender has changed their card.
+fn group_sender_name(group_id: i64, uin: i64, group_card: &str) -> String {
+    // The card of a message is empty once the member has cleared it, and the
+    // name falls back to the remark or the nickname.
+    let changed = update_member_card(group_id, uin, group_card).unwrap_or_else(|err| {
+        log::warn!("Failed to update the card of {}: {}", uin, err);
+        false
+    });
+    let name = get_member_name(group_id, uin, group_card);
+    if changed {
+        publish(AppEvent::MemberCardChanged {
+            group_id,
+            member_id: uin,
+            name: name.clone(),
+        });
+    }
+    name
+}
+
+fn first_seq(seqs: &[i32]) -> i32 {
+    seqs.first().copied().unwrap_or_default()
+}
+
+/// Save a message sent by ourselves, with the seq and time from its receipt.
+/// `ptt` is the voice of an audio message, whose `elements` are empty.
+pub(crate) fn save_sent_message(
+    target: i64,
+    kind: ChatKind,
+    receipt: &MessageReceipt,
+    elements: &MessageChain,
+    ptt: Option<&Ptt>,
+) {
+    let self_account = *ACCOUNT.get().unwrap();
+    save_to_history(MessageRecord {
+        chat_id: target,
+        kind,
+        seq: first_seq(&receipt.seqs),
+        time: receipt.time as i32,
+        sender_id: self_account,
+        sender_name: if kind.is_group() {
+            String::new()
+        } else {
+            get_friend_remark(self_account)
+        },
+        elements: encode_elements(elements, ptt),
+    });
+}
+
 #[async_trait]
 impl Handler for AppHandler {
     async fn handle(&self, event: ricq::handler::QEvent) {
         match event {
-            Login(_) => {}
-            GroupMessage(GroupMessageEvent { inner, .. }) => {
-                let main_sender = MAIN_SENDER.get().expect("failed to get main sender");
-                let content = get_contents_from(&inner.elements);
-                main_sender.input(MainMsg::GroupMessage {
+            Login(account) => log::debug!("Logged in as {}", account),
+            GroupMessage(GroupMessageEvent { client, inner }) => {
+                let mut contents = get_contents_from(&inner.elements);
+                resolve_video_urls(&client, inner.group_code, true, &mut contents).await;
+                let sender_name =
+                    group_sender_name(inner.group_code, inner.from_uin, &inner.group_card);
+                save_to_history(MessageRecord {
+                    chat_id: inner.group_code,
+                    kind: ChatKind::Group,
+                    seq: first_seq(&inner.seqs),
+                    time: inner.time,
+                    sender_id: inner.from_uin,
+                    sender_name: inner.group_card.clone(),
+                    elements: encode_elements(&inner.elements, None),
+                });
+                publish(AppEvent::GroupMessage {
                     group_id: inner.group_code,
                     message: Message {
                         sender_id: inner.from_uin,
-                        sender_name: inner.group_card,
-                        contents: content.clone(),
+                        sender_name,
+                        contents,
+                        elements: inner.elements,
                     },
                 });
-
-                // Send notification
-                if &inner.from_uin != ACCOUNT.get().unwrap() {
-                    let app = APP.get().unwrap();
-                    app.notify_group_message(inner.group_code, &get_text_from(&content));
-                }
             }
-            #[allow(unused_variables)]
             GroupAudioMessage(GroupAudioMessageEvent { client, inner }) => {
-                println!("GroupAudioMessage");
+                let url = match client
+                    .get_group_audio_url(inner.group_code, inner.audio.clone())
+                    .await
+                {
+                    Ok(url) => url,
+                    Err(err) => {
+                        log::warn!("Failed to get group audio url: {}", err);
+                        return;
+                    }
+                };
+                let sender_name =
+                    group_sender_name(inner.group_code, inner.from_uin, &inner.group_card);
+                save_to_history(MessageRecord {
+                    chat_id: inner.group_code,
+                    kind: ChatKind::Group,
+                    seq: first_seq(&inner.seqs),
+                    time: inner.time,
+                    sender_id: inner.from_uin,
+                    sender_name: inner.group_card.clone(),
+                    elements: encode_elements(&Default::default(), Some(&inner.audio.0)),
+                });
+                publish(AppEvent::GroupMessage {
+                    group_id: inner.group_code,
+                    message: Message {
+                        sender_id: inner.from_uin,
+                        sender_name,
+                        contents: vec![Content::Audio {
+                            url,
+                            duration: inner.audio.0.time.unwrap_or_default(),
+                        }],
+                        elements: Default::default(),
+                    },
+                });
             }
-            FriendMessage(FriendMessageEvent { inner, .. }) => {
-                let main_sender = MAIN_SENDER.get().expect("failed to get main sender");
+            FriendMessage(FriendMessageEvent { client, inner }) => {
                 let self_account = ACCOUNT.get().unwrap();
                 let friend_id = if inner.from_uin == *self_account {
                     inner.target
                 } else {
                     inner.from_uin
                 };
-                let contents = get_contents_from(&inner.elements);
-                main_sender.input(MainMsg::FriendMessage {
+                let mut contents = get_contents_from(&inner.elements);
+                resolve_video_urls(&client, friend_id, false, &mut contents).await;
+                let sender_name = get_friend_remark(inner.from_uin);
+                save_to_history(MessageRecord {
+                    chat_id: friend_id,
+                    kind: ChatKind::Friend,
+                    seq: first_seq(&inner.seqs),
+                    time: inner.time,
+                    sender_id: inner.from_uin,
+                    sender_name: sender_name.clone(),
+                    elements: encode_elements(&inner.elements, None),
+                });
+                publish(AppEvent::FriendMessage {
                     friend_id,
                     message: Message {
                         sender_id: inner.from_uin,
-                        sender_name: get_friend_remark(inner.from_uin),
-                        contents: contents.clone(),
+                        sender_name,
+                        contents,
+                        elements: inner.elements,
                     },
                 });
-
-                // Send notification
-                if inner.from_uin != *self_account {
-                    let app = APP.get().unwrap();
-                    app.notify_friend_message(friend_id, &get_text_from(&contents));
-                }
             }
-            #[allow(unused_variables)]
             FriendAudioMessage(FriendAudioMessageEvent { client, inner }) => {
-                println!("FriendAudioMessage");
+                let self_account = ACCOUNT.get().unwrap();
+                let friend_id = if inner.from_uin == *self_account {
+                    inner.target
+                } else {
+                    inner.from_uin
+                };
+                let url = match client
+                    .get_friend_audio_url(inner.from_uin, inner.audio.clone())
+                    .await
+                {
+                    Ok(url) => url,
+                    Err(err) => {
+                        log::warn!("Failed to get friend audio url: {}", err);
+                        return;
+                    }
+                };
+                let sender_name = get_friend_remark(inner.from_uin);
+                save_to_history(MessageRecord {
+                    chat_id: friend_id,
+                    kind: ChatKind::Friend,
+                    seq: first_seq(&inner.seqs),
+                    time: inner.time,
+                    sender_id: inner.from_uin,
+                    sender_name: sender_name.clone(),
+                    elements: encode_elements(&Default::default(), Some(&inner.audio.0)),
+                });
+                publish(AppEvent::FriendMessage {
+                    friend_id,
+                    message: Message {
+                        sender_id: inner.from_uin,
+                        sender_name,
+                        contents: vec![Content::Audio {
+                            url,
+                            duration: inner.audio.0.time.unwrap_or_default(),
+                        }],
+                        elements: Default::default(),
+                    },
+                });
             }
-            #[allow(unused_variables)]
-            GroupTempMessage(GroupTempMessageEvent { client, inner }) => {
-                println!("GroupTempMessage");
+            GroupTempMessage(GroupTempMessageEvent { inner, .. }) => {
+                save_to_history(MessageRecord {
+                    chat_id: inner.from_uin,
+                    kind: ChatKind::Temp {
+                        group_id: inner.group_code,
+                    },
+                    seq: first_seq(&inner.seqs),
+                    time: inner.time,
+                    sender_id: inner.from_uin,
+                    sender_name: inner.from_nick.clone(),
+                    elements: encode_elements(&inner.elements, None),
+                });
+                publish(AppEvent::GroupTempMessage {
+                    group_id: inner.group_code,
+                    message: Message {
+                        sender_id: inner.from_uin,
+                        sender_name: inner.from_nick,
+                        contents: get_contents_from(&inner.elements),
+                        elements: inner.elements,
+                    },
+                });
             }
-            #[allow(unused_variables)]
-            SelfInvited(SelfInvitedEvent { client, inner }) => {
-                println!("SelfInvited");
+            SelfInvited(SelfInvitedEvent { inner, .. }) => {
+                publish(AppEvent::SelfInvited {
+                    seq: inner.msg_seq,
+                    time: inner.msg_time,
+                    group_id: inner.group_code,
+                    group_name: inner.group_name,
+                    invitor_id: inner.invitor_uin,
+                    invitor_nickname: inner.invitor_nick,
+                });
             }
-            #[allow(unused_variables)]
-            NewMember(NewMemberEvent { client, inner }) => {
-                println!("NewMember");
+            NewMember(NewMemberEvent { inner, .. }) => {
+                publish(AppEvent::NewMember {
+                    group_id: inner.group_code,
+                    member_id: inner.member_uin,
+                });
             }
-            #[allow(unused_variables)]
-            GroupMute(GroupMuteEvent { client, inner }) => {
-                println!("GroupMute");
+            GroupMute(GroupMuteEvent { inner, .. }) => {
+                publish(AppEvent::GroupMute {
+                    group_id: inner.group_code,
+                    operator_id: inner.operator_uin,
+                    target_id: inner.target_uin,
+                    duration: inner.duration,
+                });
             }
-            #[allow(unused_variables)]
-            FriendMessageRecall(FriendMessageRecallEvent { client, inner }) => {
-                println!("FriendMessageRecall");
+            FriendMessageRecall(FriendMessageRecallEvent { inner, .. }) => {
+                publish(AppEvent::FriendMessageRecall {
+                    friend_id: inner.friend_uin,
+                    seq: inner.msg_seq,
+                });
             }
-            #[allow(unused_variables)]
-            GroupMessageRecall(GroupMessageRecallEvent { client, inner }) => {
-                println!("GroupMessageRecall");
+            GroupMessageRecall(GroupMessageRecallEvent { inner, .. }) => {
+                publish(AppEvent::GroupMessageRecall {
+                    group_id: inner.group_code,
+                    author_id: inner.author_uin,
+                    operator_id: inner.operator_uin,
+                    seq: inner.msg_seq,
+                });
             }
-            #[allow(unused_variables)]
-            NewFriend(NewFriendEvent { client, inner }) => {
-                println!("NewFriend");
+            NewFriend(NewFriendEvent { inner, .. }) => {
+                publish(AppEvent::NewFriend {
+                    friend_id: inner.uin,
+                    nickname: inner.nick,
+                });
             }
-            #[allow(unused_variables)]
-            GroupLeave(GroupLeaveEvent { client, inner }) => {
-                println!("GroupLeave");
+            GroupLeave(GroupLeaveEvent { inner, .. }) => {
+                publish(AppEvent::GroupLeave {
+                    group_id: inner.group_code,
+                    member_id: inner.member_uin,
+                    operator_id: inner.operator_uin,
+                });
             }
-            #[allow(unused_variables)]
-            GroupDisband(GroupDisbandEvent { client, inner }) => {
-                println!("GroupDisband");
+            GroupDisband(GroupDisbandEvent { inner, .. }) => {
+                publish(AppEvent::GroupDisband {
+                    group_id: inner.group_code,
+                    operator_id: inner.operator_uin,
+                });
             }
-            #[allow(unused_variables)]
-            FriendPoke(FriendPokeEvent { client, inner }) => {
-                println!("FriendPoke");
+            FriendPoke(FriendPokeEvent { inner, .. }) => {
+                publish(AppEvent::FriendPoke {
+                    sender_id: inner.sender,
+                    receiver_id: inner.receiver,
+                });
             }
-            #[allow(unused_variables)]
-            GroupNameUpdate(GroupNameUpdateEvent { client, inner }) => {
-                println!("GroupNameUpdate");
+            GroupNameUpdate(GroupNameUpdateEvent { inner, .. }) => {
+                publish(AppEvent::GroupNameUpdate {
+                    group_id: inner.group_code,
+                    operator_id: inner.operator_uin,
+                    name: inner.group_name,
+                });
             }
-            #[allow(unused_variables)]
-            DeleteFriend(DeleteFriendEvent { client, inner }) => {
-                println!("DeleteFriend");
+            DeleteFriend(DeleteFriendEvent { inner, .. }) => {
+                publish(AppEvent::DeleteFriend {
+                    friend_id: inner.uin,
+                });
             }
-            #[allow(unused_variables)]
-            MemberPermissionChange(MemberPermissionChangeEvent { client, inner }) => {
-                println!("MemberPermissionChange");
+            MemberPermissionChange(MemberPermissionChangeEvent { inner, .. }) => {
+                publish(AppEvent::MemberPermissionChange {
+                    group_id: inner.group_code,
+                    member_id: inner.member_uin,
+                    permission: inner.new_permission,
+                });
             }
-            #[allow(unused_variables)]
-            KickedOffline(KickedOfflineEvent { client, inner }) => {
-                println!("KickedOffline");
+            KickedOffline(KickedOfflineEvent { inner, .. }) => {
+                publish(AppEvent::KickedOffline {
+                    title: inner.title,
+                    tips: inner.tips,
+                });
             }
-            #[allow(unused_variables)]
-            MSFOffline(MSFOfflineEvent { client, inner }) => {
-                println!("MSFOffline");
+            MSFOffline(MSFOfflineEvent { inner, .. }) => {
+                publish(AppEvent::MSFOffline {
+                    title: inner.title,
+                    info: inner.info,
+                });
             }
-            #[allow(unused_variables)]
-            GroupRequest(_) => {
-                println!("GroupRequest");
+            GroupRequest(GroupRequestEvent { inner, .. }) => {
+                publish(AppEvent::GroupRequest {
+                    seq: inner.msg_seq,
+                    time: inner.msg_time,
+                    group_id: inner.group_code,
+                    group_name: inner.group_name,
+                    requester_id: inner.req_uin,
+                    requester_nickname: inner.req_nick,
+                    message: inner.message,
+                });
             }
-            #[allow(unused_variables)]
-            NewFriendRequest(_) => {
-                println!("NewFriendRequest");
+            NewFriendRequest(NewFriendRequestEvent { inner, .. }) => {
+                publish(AppEvent::NewFriendRequest {
+                    seq: inner.msg_seq,
+                    requester_id: inner.req_uin,
+                    requester_nickname: inner.req_nick,
+                    message: inner.message,
+                });
             }
         };
     }
@@ -4,7 +4,9 @@
 mod actions;
 mod app;
 mod config;
+mod connection;
 mod db;
+mod event;
 mod global;
 mod handler;
 mod utils;
@@ -14,12 +16,14 @@ use gtk::{gio, glib::Bytes, prelude::ApplicationExt};
 use relm4::{gtk, RelmApp};
 
 use app::AppModel;
-use db::sql::init_sqlite;
+use db::{sql::init_sqlite, start_contact_sync};
+use event::start_notifications;
 use global::{SharedApplication, APP};
 use resource_loader::ResourceConfig;
 
 #[tokio::main]
 async fn main() {
+    env_logger::init();
     ResourceConfig::load_or_create_default().expect("Failure on loading configuration");
     init_resources();
     init_sqlite();
@@ -30,6 +34,8 @@ async fn main() {
 
     let shared_app = SharedApplication::new(app.app.clone());
     APP.set(shared_app).unwrap();
+    start_notifications();
+    start_contact_sync();
 
     app.run(());
 }
@@ -0,0 +1,312 @@
+use std::time::Duration;
+
+use gst::prelude::*;
+use gstreamer as gst;
+use gstreamer_app as gst_app;
+
+const SILK_HEADER: &[u8] = b"#!SILK_V3";
+const AMR_HEADER: &[u8] = b"#!AMR\n";
+/// Every SILK frame holds 20ms of audio.
+const SILK_FRAME_DURATION: Duration = Duration::from_millis(20);
+/// A voice message is decoded within a fraction of this.
+const AMR_DECODE_TIMEOUT: gst::ClockTime = gst::ClockTime::from_seconds(10);
+/// The sample rate QQ's voice messages are recorded in.
+pub(crate) const SAMPLE_RATE: u32 = 24000;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) enum AudioFormat {
+    Silk,
+    Amr,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum DecodeError {
+    UnknownFormat,
+    Truncated,
+    Silk(String),
+    Amr(String),
+}
+
+impl std::error::Error for DecodeError {}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::UnknownFormat => write!(f, "Unknown audio format"),
+            DecodeError::Truncated => write!(f, "The SILK stream is truncated"),
+            DecodeError::Silk(err) => write!(f, "SILK Decode Error : {}", err),
+            DecodeError::Amr(err) => write!(f, "AMR Decode Error : {}", err),
+        }
+    }
+}
+
+/// QQ prepends an extra `0x02` byte to the standard SILK header.
+fn strip_silk_header(data: &[u8]) -> Option<&[u8]> {
+    let data = data.strip_prefix(&[0x02]).unwrap_or(data);
+    data.strip_prefix(SILK_HEADER)
+}
+
+pub(crate) fn detect_format(data: &[u8]) -> Option<AudioFormat> {
+    if strip_silk_header(data).is_some() {
+        Some(AudioFormat::Silk)
+    } else if data.starts_with(AMR_HEADER) {
+        Some(AudioFormat::Amr)
+    } else {
+        None
+    }
+}
+
+/// Split a SILK stream into its frames. Each frame is prefixed by its length
+/// as a little-endian `i16`, and a negative length marks the end of stream.
+pub(crate) fn silk_frames(data: &[u8]) -> Result<Vec<&[u8]>, DecodeError> {
+    let mut rest = strip_silk_header(data).ok_or(DecodeError::UnknownFormat)?;
+    let mut frames = Vec::new();
+
+    while rest.len() >= 2 {
+        let len = i16::from_le_bytes([rest[0], rest[1]]);
+        if len < 0 {
+            break;
+        }
+        let len = len as usize;
+        rest = &rest[2..];
+        if rest.len() < len {
+            return Err(DecodeError::Truncated);
+        }
+        frames.push(&rest[..len]);
+        rest = &rest[len..];
+    }
+
+    if rest.len() == 1 {
+        return Err(DecodeError::Truncated);
+    }
+
+    Ok(frames)
+}
+
+pub(crate) fn silk_duration(data: &[u8]) -> Result<Duration, DecodeError> {
+    Ok(SILK_FRAME_DURATION * silk_frames(data)?.len() as u32)
+}
+
+/// Decode a SILK stream into 16-bit mono PCM samples.
+pub(crate) fn decode_silk(data: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    // Validate the container first, as the SILK SDK does not report broken
+    // streams reliably.
+    silk_frames(data)?;
+    silk_rs::decode_silk(data, SAMPLE_RATE as i32).map_err(|err| DecodeError::Silk(err.to_string()))
+}
+
+fn amr_error(err: impl std::fmt::Display) -> DecodeError {
+    DecodeError::Amr(err.to_string())
+}
+
+/// Decode an AMR-NB stream into 16-bit mono PCM samples at [`SAMPLE_RATE`].
+/// No AMR decoder is written in Rust, so this drives GStreamer's `amrnbdec`
+/// in memory, instead of leaving the format to the player.
+pub(crate) fn decode_amr(data: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    if !data.starts_with(AMR_HEADER) {
+        return Err(DecodeError::UnknownFormat);
+    }
+    gst::init().map_err(amr_error)?;
+
+    let pipeline = gst::parse_launch(
+        "appsrc name=src ! amrparse ! amrnbdec ! audioconvert ! audioresample ! appsink name=sink",
+    )
+    .map_err(amr_error)?
+    .downcast::<gst::Pipeline>()
+    .unwrap();
+    let src = pipeline
+        .by_name("src")
+        .and_then(|src| src.downcast::<gst_app::AppSrc>().ok())
+        .unwrap();
+    let sink = pipeline
+        .by_name("sink")
+        .and_then(|sink| sink.downcast::<gst_app::AppSink>().ok())
+        .unwrap();
+    sink.set_caps(Some(
+        &gst::Caps::builder("audio/x-raw")
+            .field("format", "S16LE")
+            .field("layout", "interleaved")
+            .field("channels", 1i32)
+            .field("rate", SAMPLE_RATE as i32)
+            .build(),
+    ));
+    sink.set_property("sync", false);
+
+    pipeline.set_state(gst::State::Playing).map_err(amr_error)?;
+    let pushed = src
+        .push_buffer(gst::Buffer::from_slice(data.to_vec()))
+        .and_then(|_| src.end_of_stream());
+
+    // The appsink keeps every sample until it is pulled, so they are all
+    // there once the pipeline has finished. Pulling them blockingly would
+    // wait forever if the pipeline fails instead.
+    let message = pipeline.bus().unwrap().timed_pop_filtered(
+        AMR_DECODE_TIMEOUT,
+        &[gst::MessageType::Eos, gst::MessageType::Error],
+    );
+    let error = match message.as_ref().map(|message| message.view()) {
+        Some(gst::MessageView::Error(err)) => Some(err.error().to_string()),
+        Some(_) => None,
+        None => Some("Timed out".to_string()),
+    };
+
+    let mut pcm = Vec::new();
+    while let Some(sample) = sink.try_pull_sample(gst::ClockTime::ZERO) {
+        if let Some(buffer) = sample.buffer() {
+            pcm.extend_from_slice(&buffer.map_readable().map_err(amr_error)?);
+        }
+    }
+    pipeline.set_state(gst::State::Null).ok();
+
+    pushed.map_err(amr_error)?;
+    match error {
+        Some(err) => Err(DecodeError::Amr(err)),
+        None => Ok(pcm),
+    }
+}
+
+/// Wrap 16-bit mono PCM samples into a WAV file, which GStreamer can play directly.
+pub(crate) fn pcm_to_wav(pcm: &[u8], sample_rate: u32) -> Vec<u8> {
+    const CHANNELS: u16 = 1;
+    const BITS_PER_SAMPLE: u16 = 16;
+    let block_align = CHANNELS * BITS_PER_SAMPLE / 8;
+    let byte_rate = sample_rate * block_align as u32;
+
+    let mut wav = Vec::with_capacity(44 + pcm.len());
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + pcm.len() as u32).to_le_bytes());
+    wav.extend_from_slice(b"WAVE");
+    wav.extend_from_slice(b"fmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    // PCM
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&CHANNELS.to_le_bytes());
+    wav.extend_from_slice(&sample_rate.to_le_bytes());
+    wav.extend_from_slice(&byte_rate.to_le_bytes());
+    wav.extend_from_slice(&block_align.to_le_bytes());
+    wav.extend_from_slice(&BITS_PER_SAMPLE.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&(pcm.len() as u32).to_le_bytes());
+    wav.extend_from_slice(pcm);
+    wav
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::PI;
+    use std::time::Duration;
+
+    use super::{
+        decode_amr, decode_silk, detect_format, gst, pcm_to_wav, silk_duration, silk_frames,
+        AudioFormat, DecodeError, SAMPLE_RATE,
+    };
+
+    const TWO_FRAMES: &[u8] = include_bytes!("fixtures/two_frames.silk");
+    const TENCENT_HEADER: &[u8] = include_bytes!("fixtures/tencent_header.silk");
+    const TRUNCATED: &[u8] = include_bytes!("fixtures/truncated.silk");
+    /// 50 AMR-NB frames at 12.2 kbit/s, whose speech parameters are arbitrary.
+    const AMR: &[u8] = include_bytes!("fixtures/one_second.amr");
+
+    /// One second of a 440Hz tone, as 16-bit PCM at `SAMPLE_RATE`.
+    fn tone() -> Vec<i16> {
+        (0..SAMPLE_RATE)
+            .map(|i| {
+                let t = i as f64 / SAMPLE_RATE as f64;
+                ((t * 440.0 * 2.0 * PI).sin() * 8000.0) as i16
+            })
+            .collect()
+    }
+
+    fn rms(samples: &[i16]) -> f64 {
+        let sum: f64 = samples.iter().map(|&s| s as f64 * s as f64).sum();
+        (sum / samples.len() as f64).sqrt()
+    }
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(detect_format(TWO_FRAMES), Some(AudioFormat::Silk));
+        assert_eq!(detect_format(TENCENT_HEADER), Some(AudioFormat::Silk));
+        assert_eq!(detect_format(AMR), Some(AudioFormat::Amr));
+        assert_eq!(detect_format(b"RIFF"), None);
+    }
+
+    #[test]
+    fn test_silk_frames() {
+        let frames = silk_frames(TWO_FRAMES).unwrap();
+        assert_eq!(frames, vec![&[1u8, 2, 3][..], &[4u8, 5][..]]);
+
+        let frames = silk_frames(TENCENT_HEADER).unwrap();
+        assert_eq!(frames.len(), 3);
+    }
+
+    #[test]
+    fn test_silk_duration() {
+        assert_eq!(silk_duration(TWO_FRAMES), Ok(Duration::from_millis(40)));
+        assert_eq!(silk_duration(TENCENT_HEADER), Ok(Duration::from_millis(60)));
+    }
+
+    #[test]
+    fn test_broken_silk() {
+        assert_eq!(silk_frames(TRUNCATED), Err(DecodeError::Truncated));
+        assert_eq!(decode_silk(TRUNCATED), Err(DecodeError::Truncated));
+        assert_eq!(decode_silk(AMR), Err(DecodeError::UnknownFormat));
+    }
+
+    #[test]
+    fn test_decode_silk() {
+        // A real stream, in QQ's flavour, from the encoder of the SILK SDK.
+        let pcm: Vec<u8> = tone().iter().flat_map(|s| s.to_le_bytes()).collect();
+        let silk = silk_rs::encode_silk(&pcm, SAMPLE_RATE as i32, 24000, true).unwrap();
+        assert_eq!(detect_format(&silk), Some(AudioFormat::Silk));
+        assert_eq!(silk_duration(&silk), Ok(Duration::from_secs(1)));
+
+        let decoded = decode_silk(&silk).unwrap();
+        assert_eq!(decoded.len(), pcm.len());
+        let decoded: Vec<i16> = decoded
+            .chunks_exact(2)
+            .map(|s| i16::from_le_bytes([s[0], s[1]]))
+            .collect();
+        // A lossy codec keeps the loudness of the tone, not its samples.
+        let ratio = rms(&decoded) / rms(&tone());
+        assert!((0.5..1.5).contains(&ratio), "ratio = {}", ratio);
+    }
+
+    #[test]
+    fn test_decode_amr() {
+        assert_eq!(decode_amr(TWO_FRAMES), Err(DecodeError::UnknownFormat));
+
+        gst::init().unwrap();
+        if gst::ElementFactory::find("amrnbdec").is_none() {
+            eprintln!("amrnbdec is not installed, skipping");
+            return;
+        }
+        let decoded = decode_amr(AMR).unwrap();
+        let decoded: Vec<i16> = decoded
+            .chunks_exact(2)
+            .map(|s| i16::from_le_bytes([s[0], s[1]]))
+            .collect();
+        // One second, give or take a frame lost to the resampler.
+        let frame = SAMPLE_RATE as i64 / 50;
+        assert!(
+            (decoded.len() as i64 - SAMPLE_RATE as i64).abs() <= frame,
+            "len = {}",
+            decoded.len()
+        );
+        assert!(rms(&decoded) > 0.0);
+    }
+
+    #[test]
+    fn test_pcm_to_wav() {
+        let pcm = [0u8, 1, 2, 3];
+        let wav = pcm_to_wav(&pcm, 24000);
+
+        assert_eq!(wav.len(), 44 + pcm.len());
+        assert_eq!(&wav[0..4], b"RIFF");
+        assert_eq!(&wav[4..8], &40u32.to_le_bytes());
+        assert_eq!(&wav[8..16], b"WAVEfmt ");
+        assert_eq!(&wav[24..28], &24000u32.to_le_bytes());
+        assert_eq!(&wav[28..32], &48000u32.to_le_bytes());
+        assert_eq!(&wav[36..40], b"data");
+        assert_eq!(&wav[44..], &pcm);
+    }
+}
@@ -0,0 +1,8 @@
+mod decoder;
+mod player;
+
+pub(crate) use decoder::{
+    decode_amr, decode_silk, detect_format, pcm_to_wav, silk_duration, AudioFormat, DecodeError,
+    SAMPLE_RATE,
+};
+pub(crate) use player::AudioPlayer;
@@ -0,0 +1,80 @@
+use std::cell::RefCell;
+use std::path::Path;
+
+use gst::prelude::*;
+use gstreamer as gst;
+use relm4::gtk::glib;
+
+/// A thin wrapper of GStreamer's `playbin`, which plays a single local file.
+#[derive(Debug)]
+pub(crate) struct AudioPlayer {
+    playbin: gst::Element,
+    /// The watch of `connect_eos`, removed with the player.
+    watch: RefCell<Option<glib::SourceId>>,
+}
+
+impl AudioPlayer {
+    pub(crate) fn new(path: &Path) -> Result<Self, glib::Error> {
+        gst::init()?;
+
+        let playbin = gst::ElementFactory::make("playbin", None)
+            .map_err(|err| glib::Error::new(gst::CoreError::MissingPlugin, &err.message))?;
+        let uri = glib::filename_to_uri(path, None)?;
+        playbin.set_property("uri", uri.as_str());
+
+        Ok(AudioPlayer {
+            playbin,
+            watch: RefCell::new(None),
+        })
+    }
+
+    /// Call `callback` on the main thread when the playback reaches its end.
+    pub(crate) fn connect_eos<F: Fn() + 'static>(&self, callback: F) {
+        let bus = self.playbin.bus().unwrap();
+        // A bus only takes a single watch.
+        if let Some(old_watch) = self.watch.take() {
+            old_watch.remove();
+        }
+        let playbin = self.playbin.downgrade();
+        let watch = bus
+            .add_watch_local(move |_, message| {
+                use gst::MessageView;
+                let playbin = match playbin.upgrade() {
+                    Some(playbin) => playbin,
+                    None => return glib::Continue(false),
+                };
+                match message.view() {
+                    MessageView::Eos(..) => {
+                        playbin.set_state(gst::State::Ready).ok();
+                        callback();
+                    }
+                    MessageView::Error(err) => {
+                        log::warn!("Audio Playback Error : {}", err.error());
+                        playbin.set_state(gst::State::Ready).ok();
+                        callback();
+                    }
+                    _ => {}
+                }
+                glib::Continue(true)
+            })
+            .expect("failed to add bus watch");
+        self.watch.replace(Some(watch));
+    }
+
+    pub(crate) fn play(&self) {
+        self.playbin.set_state(gst::State::Playing).ok();
+    }
+
+    pub(crate) fn pause(&self) {
+        self.playbin.set_state(gst::State::Paused).ok();
+    }
+}
+
+impl Drop for AudioPlayer {
+    fn drop(&mut self) {
+        if let Some(watch) = self.watch.take() {
+            watch.remove();
+        }
+        self.playbin.set_state(gst::State::Null).ok();
+    }
+}
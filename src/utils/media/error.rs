@@ -0,0 +1,40 @@
+use std::io;
+
+use crate::utils::audio::DecodeError;
+
+#[derive(Debug)]
+pub enum MediaError {
+    Io(io::Error),
+    Request(reqwest::Error),
+    Decode(DecodeError),
+}
+
+impl std::error::Error for MediaError {}
+
+impl std::fmt::Display for MediaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MediaError::Io(err) => write!(f, "Media Io Error : {}", err),
+            MediaError::Request(err) => write!(f, "Media Request Error : {}", err),
+            MediaError::Decode(err) => write!(f, "Media Decode Error : {}", err),
+        }
+    }
+}
+
+impl From<io::Error> for MediaError {
+    fn from(err: io::Error) -> Self {
+        MediaError::Io(err)
+    }
+}
+
+impl From<reqwest::Error> for MediaError {
+    fn from(err: reqwest::Error) -> Self {
+        MediaError::Request(err)
+    }
+}
+
+impl From<DecodeError> for MediaError {
+    fn from(err: DecodeError) -> Self {
+        MediaError::Decode(err)
+    }
+}
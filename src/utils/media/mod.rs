@@ -0,0 +1,189 @@
+pub mod error;
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use gstreamer as gst;
+use gstreamer_pbutils::Discoverer;
+use relm4::gtk::{
+    glib::{self, MainContext, PRIORITY_DEFAULT},
+    prelude::*,
+    Picture,
+};
+use resource_loader::{MediaAudio, MediaImage, MediaVideo, SyncCreatePath};
+use tokio::{io::AsyncWriteExt, task};
+
+use self::error::MediaError;
+use crate::utils::audio::{
+    decode_amr, decode_silk, detect_format, pcm_to_wav, silk_duration, AudioFormat, DecodeError,
+    SAMPLE_RATE,
+};
+use crate::utils::message::Video;
+use crate::utils::DirAction;
+
+/// The cached files are named after the hash of their urls, since the urls
+/// of QQ's media rarely contain a usable filename.
+fn cache_filename(url: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    url.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+pub fn get_image_path(url: &str, action: DirAction) -> io::Result<PathBuf> {
+    MediaImage::do_action_and_get_path(action).map(|dir| dir.join(cache_filename(url)))
+}
+
+pub async fn download_image(url: String) -> Result<PathBuf, MediaError> {
+    let path = get_image_path(&url, DirAction::CreateAll)?;
+    if !path.exists() {
+        log::debug!("Downloading {}", url);
+        let body = reqwest::get(&url).await?.bytes().await?;
+        tokio::fs::write(&path, &body).await?;
+    }
+
+    Ok(path)
+}
+
+/// Download the voice message of `url` and decode it into a WAV file, which
+/// GStreamer is able to play. The duration is returned when it is known.
+pub async fn download_audio(url: String) -> Result<(PathBuf, Option<Duration>), MediaError> {
+    let dir = MediaAudio::do_action_and_get_path(DirAction::CreateAll)?;
+    let wav_path = dir.join(format!("{}.wav", cache_filename(&url)));
+    if wav_path.exists() {
+        let pcm_len = tokio::fs::metadata(&wav_path)
+            .await?
+            .len()
+            .saturating_sub(44);
+        return Ok((wav_path, Some(pcm_duration(pcm_len))));
+    }
+
+    log::debug!("Downloading {}", url);
+    let body = reqwest::get(&url).await?.bytes().await?;
+
+    let format = detect_format(&body).ok_or(DecodeError::UnknownFormat)?;
+    let duration = match format {
+        AudioFormat::Silk => silk_duration(&body).ok(),
+        AudioFormat::Amr => None,
+    };
+    let pcm = task::spawn_blocking(move || match format {
+        AudioFormat::Silk => decode_silk(&body),
+        AudioFormat::Amr => decode_amr(&body),
+    })
+    .await
+    .map_err(|err| io::Error::new(io::ErrorKind::Other, err))??;
+    tokio::fs::write(&wav_path, pcm_to_wav(&pcm, SAMPLE_RATE)).await?;
+
+    Ok((
+        wav_path,
+        Some(duration.unwrap_or_else(|| pcm_duration(pcm.len() as u64))),
+    ))
+}
+
+/// The duration of `len` bytes of 16-bit mono PCM at `SAMPLE_RATE`.
+fn pcm_duration(len: u64) -> Duration {
+    Duration::from_secs_f64(len as f64 / (SAMPLE_RATE as f64 * 2.0))
+}
+
+/// The extension of a file name chosen by the sender, if it is safe to be
+/// part of a path.
+fn safe_extension(name: &str) -> Option<String> {
+    let extension = Path::new(name).extension()?.to_str()?;
+    let is_safe = extension.len() <= 8 && extension.chars().all(|c| c.is_ascii_alphanumeric());
+    (is_safe && !extension.is_empty()).then(|| extension.to_ascii_lowercase())
+}
+
+/// The cached video is named after its md5, keeping the extension so that
+/// the default application recognizes it.
+pub fn get_video_path(video: &Video, action: DirAction) -> io::Result<PathBuf> {
+    let filename = match safe_extension(&video.name) {
+        Some(extension) => format!("{}.{}", video.md5, extension),
+        None => video.md5.clone(),
+    };
+    MediaVideo::do_action_and_get_path(action).map(|dir| dir.join(filename))
+}
+
+/// Download the video of `url` into `path`. `progress` is called with the
+/// downloaded fraction whenever a new chunk arrives.
+pub async fn download_video(
+    url: String,
+    path: PathBuf,
+    progress: impl Fn(f64),
+) -> Result<PathBuf, MediaError> {
+    log::debug!("Downloading {}", url);
+    let mut response = reqwest::get(&url).await?;
+    let total = response.content_length();
+
+    // Write into a partial file first, so that an interrupted download is
+    // never mistaken for a cached video.
+    let part_path = path.with_extension("part");
+    let mut file = tokio::fs::File::create(&part_path).await?;
+    let mut downloaded = 0;
+    while let Some(chunk) = response.chunk().await? {
+        file.write_all(&chunk).await?;
+        downloaded += chunk.len() as u64;
+        if let Some(total) = total {
+            progress(downloaded as f64 / total as f64);
+        }
+    }
+    file.flush().await?;
+    tokio::fs::rename(&part_path, &path).await?;
+
+    Ok(path)
+}
+
+/// Read the duration of a local media file. This blocks until GStreamer
+/// finishes probing the file.
+pub fn probe_duration(path: &Path) -> Option<Duration> {
+    gst::init().ok()?;
+    let uri = glib::filename_to_uri(path, None).ok()?;
+    let discoverer = Discoverer::new(gst::ClockTime::from_seconds(5)).ok()?;
+    let info = discoverer.discover_uri(&uri).ok()?;
+    info.duration()
+        .map(|duration| Duration::from_secs(duration.seconds()))
+}
+
+/// Show the image of `url` in `picture`, downloading it into the media cache
+/// first if necessary.
+pub(crate) fn load_picture(picture: &Picture, url: String) {
+    if let Ok(path) = get_image_path(&url, DirAction::None) {
+        if path.exists() {
+            picture.set_filename(Some(&path));
+            return;
+        }
+    }
+
+    let (tx, rx) = MainContext::channel(PRIORITY_DEFAULT);
+    task::spawn(async move {
+        match download_image(url).await {
+            Ok(path) => {
+                tx.send(path).ok();
+            }
+            Err(err) => log::warn!("{}", err),
+        }
+    });
+
+    let picture = picture.clone();
+    rx.attach(None, move |path| {
+        picture.set_filename(Some(&path));
+        glib::Continue(false)
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::safe_extension;
+
+    #[test]
+    fn test_safe_extension() {
+        assert_eq!(safe_extension("clip.MP4"), Some("mp4".to_string()));
+        assert_eq!(safe_extension("../../.bashrc"), None);
+        assert_eq!(safe_extension("a.mp4/../../x"), None);
+        assert_eq!(safe_extension("clip.m p4"), None);
+        assert_eq!(safe_extension("clip"), None);
+    }
+}
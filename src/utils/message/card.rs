@@ -0,0 +1,188 @@
+use serde_json::Value;
+
+/// The shared content of `LightApp` and `RichMsg` messages, such as links,
+/// mini programs, music and group announcements.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Card {
+    pub title: String,
+    pub description: String,
+    pub preview: Option<String>,
+    pub url: Option<String>,
+    pub source: Option<String>,
+}
+
+/// Some of the urls in the payloads come without a scheme.
+fn normalize_url(url: &str) -> String {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        url.to_string()
+    } else {
+        format!("https://{}", url)
+    }
+}
+
+fn get_str(value: &Value, keys: &[&str]) -> Option<String> {
+    keys.iter()
+        .filter_map(|key| value.get(key).and_then(Value::as_str))
+        .find(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+fn decode_base64(value: &Value, key: &str) -> Option<String> {
+    let bytes = base64::decode(value.get(key)?.as_str()?).ok()?;
+    String::from_utf8(bytes).ok()
+}
+
+/// Parse the JSON payload of a `LightApp` message.
+pub(crate) fn parse_light_app(content: &str) -> Option<Card> {
+    let value: Value = serde_json::from_str(content).ok()?;
+    let meta = value.get("meta")?.as_object()?;
+
+    if let Some(announce) = meta.get("mannounce") {
+        return Some(Card {
+            title: decode_base64(announce, "title")?,
+            description: decode_base64(announce, "text").unwrap_or_default(),
+            preview: None,
+            url: None,
+            source: Some("群公告".to_string()),
+        });
+    }
+
+    // News, music and mini program shares all keep their details in the
+    // only object inside `meta`, e.g. `meta.news`, `meta.music` or `meta.detail_1`.
+    let detail = meta.values().find(|value| value.is_object())?;
+
+    Some(Card {
+        title: get_str(detail, &["title"])?,
+        description: get_str(detail, &["desc"]).unwrap_or_default(),
+        preview: get_str(detail, &["preview"]).map(|url| normalize_url(&url)),
+        url: get_str(detail, &["jumpUrl", "qqdocurl", "url"]).map(|url| normalize_url(&url)),
+        source: get_str(detail, &["tag"]),
+    })
+}
+
+/// Parse the XML payload of a `RichMsg` message.
+pub(crate) fn parse_rich_msg(template: &str) -> Option<Card> {
+    // Some of the rich messages carry the same JSON as `LightApp` does.
+    if template.starts_with('{') {
+        return parse_light_app(template);
+    }
+
+    let document = roxmltree::Document::parse(template).ok()?;
+    let msg = document.root_element();
+    if !msg.has_tag_name("msg") {
+        return None;
+    }
+
+    let find = |tag: &str| document.descendants().find(|node| node.has_tag_name(tag));
+    let text_of = |tag: &str| {
+        find(tag)
+            .and_then(|node| node.text())
+            .map(str::trim)
+            .filter(|text| !text.is_empty())
+            .map(str::to_string)
+    };
+
+    let title = text_of("title").or_else(|| msg.attribute("brief").map(str::to_string))?;
+
+    Some(Card {
+        title,
+        description: text_of("summary").unwrap_or_default(),
+        preview: find("picture")
+            .and_then(|node| node.attribute("cover"))
+            .filter(|url| !url.is_empty())
+            .map(normalize_url),
+        url: msg
+            .attribute("url")
+            .filter(|url| !url.is_empty())
+            .map(normalize_url),
+        source: find("source")
+            .and_then(|node| node.attribute("name"))
+            .filter(|name| !name.is_empty())
+            .map(str::to_string),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_light_app, parse_rich_msg, Card};
+
+    #[test]
+    fn test_light_app_news() {
+        let content = r#"{
+            "app": "com.tencent.structmsg",
+            "view": "news",
+            "meta": {
+                "news": {
+                    "title": "Gtk QQ",
+                    "desc": "Unofficial Linux QQ client",
+                    "preview": "p.qpic.cn/preview.png",
+                    "jumpUrl": "https://github.com/lomirus/gtk-qq",
+                    "tag": "Github"
+                }
+            },
+            "prompt": "[分享]Gtk QQ"
+        }"#;
+
+        assert_eq!(
+            parse_light_app(content),
+            Some(Card {
+                title: "Gtk QQ".to_string(),
+                description: "Unofficial Linux QQ client".to_string(),
+                preview: Some("https://p.qpic.cn/preview.png".to_string()),
+                url: Some("https://github.com/lomirus/gtk-qq".to_string()),
+                source: Some("Github".to_string()),
+            })
+        )
+    }
+
+    #[test]
+    fn test_light_app_announcement() {
+        // "公告" and "明天放假" in base64
+        let content = r#"{
+            "app": "com.tencent.mannounce",
+            "meta": { "mannounce": { "title": "5YWs5ZGK", "text": "5piO5aSp5pS+5YGH" } }
+        }"#;
+
+        let card = parse_light_app(content).unwrap();
+        assert_eq!(card.title, "公告");
+        assert_eq!(card.description, "明天放假");
+        assert_eq!(card.url, None);
+    }
+
+    #[test]
+    fn test_light_app_unknown() {
+        assert_eq!(parse_light_app(r#"{"app": "com.tencent.unknown"}"#), None);
+        assert_eq!(parse_light_app("not json at all"), None);
+    }
+
+    #[test]
+    fn test_rich_msg() {
+        let template = r#"<?xml version='1.0' encoding='UTF-8' standalone='yes' ?>
+            <msg serviceID="1" templateID="1" action="web" brief="[分享] Gtk QQ"
+                 url="https://github.com/lomirus/gtk-qq">
+                <item layout="2">
+                    <picture cover="https://p.qpic.cn/cover.png" />
+                    <title>Gtk QQ</title>
+                    <summary>Unofficial Linux QQ client</summary>
+                </item>
+                <source name="Github" />
+            </msg>"#;
+
+        assert_eq!(
+            parse_rich_msg(template),
+            Some(Card {
+                title: "Gtk QQ".to_string(),
+                description: "Unofficial Linux QQ client".to_string(),
+                preview: Some("https://p.qpic.cn/cover.png".to_string()),
+                url: Some("https://github.com/lomirus/gtk-qq".to_string()),
+                source: Some("Github".to_string()),
+            })
+        )
+    }
+
+    #[test]
+    fn test_rich_msg_unknown() {
+        assert_eq!(parse_rich_msg("<unknown />"), None);
+        assert_eq!(parse_rich_msg("<msg><item /></msg>"), None);
+    }
+}
@@ -1,8 +1,21 @@
+use super::{Card, Forward, Video};
+
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub(crate) enum Content {
     Text(String),
-    Image { url: String, filename: String },
+    Image {
+        url: String,
+        filename: String,
+    },
+    Card(Card),
+    /// `duration` is in seconds.
+    Audio {
+        url: String,
+        duration: i32,
+    },
+    Video(Video),
+    Forward(Forward),
 }
 
 impl Content {
@@ -10,6 +23,10 @@ impl Content {
         match self {
             Content::Text(text) => text.clone(),
             Content::Image { .. } => "[图片]".to_string(),
+            Content::Card(card) => format!("[{}]", card.title),
+            Content::Audio { .. } => "[语音]".to_string(),
+            Content::Video(_) => "[视频文件]".to_string(),
+            Content::Forward(_) => "[聊天记录]".to_string(),
         }
     }
 }
@@ -0,0 +1,158 @@
+use ricq::structs::{ForwardMessage, ForwardNode};
+
+use super::{get_contents_from, get_text_from};
+
+/// Where the messages of a forwarded bundle come from.
+#[derive(Debug, Clone)]
+pub(crate) enum ForwardSource {
+    /// The bundle has to be downloaded from the server by its resource id.
+    ResId(String),
+    /// The bundle is nested in another one, so its messages are already here.
+    Nodes(Vec<ForwardMessage>),
+}
+
+/// A merged/forwarded chat record, a.k.a. "聊天记录".
+#[derive(Debug, Clone)]
+pub(crate) struct Forward {
+    /// Lines like "Alice: Hello" previewing the first few messages.
+    pub preview: Vec<String>,
+    pub summary: String,
+    pub source: ForwardSource,
+    fallback_title: String,
+}
+
+impl Forward {
+    /// Build a title like "Chat history of Alice and Bob" from the senders
+    /// in the preview.
+    pub(crate) fn title(&self) -> String {
+        let mut names: Vec<&str> = Vec::new();
+        for line in self.preview.iter() {
+            if let Some((name, _)) = line.split_once(':') {
+                let name = name.trim();
+                if !name.is_empty() && !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+        }
+
+        match names.as_slice() {
+            [] => self.fallback_title.clone(),
+            [name] => format!("Chat history of {}", name),
+            [init @ .., last] if init.len() < 3 => {
+                format!("Chat history of {} and {}", init.join(", "), last)
+            }
+            _ => format!("Chat history of {} and others", names[..3].join(", ")),
+        }
+    }
+}
+
+fn node_preview(node: &ForwardMessage) -> String {
+    match node {
+        ForwardMessage::Message(message) => format!(
+            "{}: {}",
+            message.sender_name,
+            get_text_from(&get_contents_from(&message.elements))
+        ),
+        ForwardMessage::Forward(forward) => format!("{}: [聊天记录]", forward.sender_name),
+    }
+}
+
+impl From<ForwardNode> for Forward {
+    fn from(node: ForwardNode) -> Self {
+        Forward {
+            preview: node.nodes.iter().take(4).map(node_preview).collect(),
+            summary: format!("{} messages", node.nodes.len()),
+            source: ForwardSource::Nodes(node.nodes),
+            fallback_title: "Chat history".to_string(),
+        }
+    }
+}
+
+/// Parse the XML payload of a `RichMsg` which carries a forwarded bundle.
+pub(crate) fn parse_forward(template: &str) -> Option<Forward> {
+    let document = roxmltree::Document::parse(template).ok()?;
+    let msg = document.root_element();
+    let res_id = msg.attribute("m_resid").filter(|id| !id.is_empty())?;
+
+    let mut titles = document
+        .descendants()
+        .filter(|node| node.has_tag_name("title"))
+        .filter_map(|node| node.text())
+        .map(|text| text.trim().to_string());
+    // The first title is the header, and the rest are the preview.
+    let fallback_title = titles.next().unwrap_or_else(|| "Chat history".to_string());
+    let preview = titles.collect();
+
+    let summary = document
+        .descendants()
+        .find(|node| node.has_tag_name("summary"))
+        .and_then(|node| node.text())
+        .unwrap_or_default()
+        .trim()
+        .to_string();
+
+    Some(Forward {
+        preview,
+        summary,
+        source: ForwardSource::ResId(res_id.to_string()),
+        fallback_title,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_forward, Forward, ForwardSource};
+
+    fn forward_with(preview: &[&str]) -> Forward {
+        Forward {
+            preview: preview.iter().map(|line| line.to_string()).collect(),
+            summary: String::new(),
+            source: ForwardSource::ResId(String::new()),
+            fallback_title: "群聊的聊天记录".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_forward() {
+        let template = r##"<?xml version='1.0' encoding='UTF-8' standalone='yes' ?>
+            <msg serviceID="35" templateID="1" action="viewMultiMsg" brief="[聊天记录]"
+                 m_resid="abc/def" m_fileName="123" tSum="2" flag="3">
+                <item layout="1">
+                    <title color="#000000" size="34">群聊的聊天记录</title>
+                    <title color="#777777" size="26">Alice: Hello</title>
+                    <title color="#777777" size="26">Bob: Hi</title>
+                    <hr />
+                    <summary color="#808080">查看2条转发消息</summary>
+                </item>
+                <source name="聊天记录" />
+            </msg>"##;
+
+        let forward = parse_forward(template).unwrap();
+        assert_eq!(forward.preview, vec!["Alice: Hello", "Bob: Hi"]);
+        assert_eq!(forward.summary, "查看2条转发消息");
+        assert!(matches!(forward.source, ForwardSource::ResId(ref id) if id == "abc/def"));
+        assert_eq!(forward.title(), "Chat history of Alice and Bob");
+    }
+
+    #[test]
+    fn test_parse_not_forward() {
+        assert!(parse_forward(r#"<msg serviceID="1" url="https://example.com" />"#).is_none());
+    }
+
+    #[test]
+    fn test_title() {
+        assert_eq!(forward_with(&[]).title(), "群聊的聊天记录");
+        assert_eq!(
+            forward_with(&["Alice: a", "Alice: b"]).title(),
+            "Chat history of Alice"
+        );
+        assert_eq!(
+            forward_with(&["A: 1", "B: 2", "C: 3"]).title(),
+            "Chat history of A, B and C"
+        );
+        assert_eq!(
+            forward_with(&["A: 1", "B: 2", "C: 3", "D: 4"]).title(),
+            "Chat history of A, B, C and others"
+        );
+    }
+}
@@ -0,0 +1,58 @@
+use prost::Message as _;
+use ricq::msg::MessageChain;
+use ricq::pb::msg::{Elem, Ptt, RichText};
+
+/// Encode the elements of a message, as well as the voice of an audio message,
+/// into the blob stored in the local history.
+pub(crate) fn encode_elements(elements: &MessageChain, ptt: Option<&Ptt>) -> Vec<u8> {
+    RichText {
+        elems: Vec::<Elem>::from(elements.clone()),
+        ptt: ptt.cloned(),
+        ..Default::default()
+    }
+    .encode_to_vec()
+}
+
+pub(crate) fn decode_elements(bytes: &[u8]) -> Option<(MessageChain, Option<Ptt>)> {
+    let rich_text = RichText::decode(bytes).ok()?;
+    Some((MessageChain::from(rich_text.elems), rich_text.ptt))
+}
+
+#[cfg(test)]
+mod test {
+    use ricq::msg::{elem, MessageChain};
+    use ricq::pb::msg::Ptt;
+
+    use super::{decode_elements, encode_elements};
+    use crate::utils::message::{get_contents_from, Content};
+
+    #[test]
+    fn test_text_round_trip() {
+        let chain = MessageChain::new(elem::Text::new("Hello".to_string()));
+        let (decoded, ptt) = decode_elements(&encode_elements(&chain, None)).unwrap();
+
+        assert!(ptt.is_none());
+        assert!(matches!(
+            get_contents_from(&decoded).as_slice(),
+            [Content::Text(text)] if text == "Hello"
+        ));
+    }
+
+    #[test]
+    fn test_ptt_round_trip() {
+        let ptt = Ptt {
+            time: Some(3),
+            ..Default::default()
+        };
+        let (decoded, ptt) =
+            decode_elements(&encode_elements(&MessageChain::default(), Some(&ptt))).unwrap();
+
+        assert!(get_contents_from(&decoded).is_empty());
+        assert_eq!(ptt.and_then(|ptt| ptt.time), Some(3));
+    }
+
+    #[test]
+    fn test_decode_garbage() {
+        assert!(decode_elements(&[0xff, 0xff, 0xff]).is_none());
+    }
+}
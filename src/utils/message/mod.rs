@@ -1,14 +1,30 @@
+mod card;
 mod content;
+mod forward;
+mod history;
+mod notice;
 mod utils;
+mod video;
 
+pub(crate) use self::card::Card;
 pub(crate) use self::content::get_text_from;
 pub(crate) use self::content::Content;
+pub(crate) use self::forward::{parse_forward, Forward, ForwardSource};
+pub(crate) use self::history::{decode_elements, encode_elements};
+pub(crate) use self::notice::{describe_group_event, describe_group_left};
 pub(crate) use self::utils::get_contents_from;
+pub(crate) use self::video::{resolve_video_urls, Video};
+
+use ricq::msg::MessageChain;
+
 #[derive(Clone, Debug)]
 pub(crate) struct Message {
     pub sender_id: i64,
     pub sender_name: String,
     pub contents: Vec<Content>,
+    /// The elements the contents are parsed from, which are forwarded as
+    /// they are. It is empty for voice messages.
+    pub elements: MessageChain,
 }
 
 impl Message {
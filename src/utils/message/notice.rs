@@ -0,0 +1,197 @@
+use std::time::Duration;
+
+use ricq::structs::GroupMemberPermission;
+
+use crate::db::sql::GroupLeft;
+use crate::event::AppEvent;
+
+/// Format a mute duration like "10 minutes" or "1 day", with its largest unit.
+pub(crate) fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (count, unit) = match secs {
+        0..=59 => (secs, "second"),
+        60..=3599 => (secs / 60, "minute"),
+        3600..=86399 => (secs / 3600, "hour"),
+        _ => (secs / 86400, "day"),
+    };
+
+    if count == 1 {
+        format!("1 {}", unit)
+    } else {
+        format!("{} {}s", count, unit)
+    }
+}
+
+/// The notice of a chatroom whose group is disbanded or left.
+pub(crate) fn describe_group_left(left: GroupLeft) -> &'static str {
+    match left {
+        GroupLeft::Disbanded => "The group was disbanded",
+        GroupLeft::Left => "You are no longer a member of this group",
+    }
+}
+
+/// Describe a group system event as a notice like "Alice joined", returning
+/// the group the notice belongs to. `name_of` resolves the names of members.
+pub(crate) fn describe_group_event(
+    event: &AppEvent,
+    name_of: impl Fn(i64) -> String,
+) -> Option<(i64, String)> {
+    let notice = match event {
+        AppEvent::NewMember {
+            group_id,
+            member_id,
+        } => (*group_id, format!("{} joined", name_of(*member_id))),
+        AppEvent::GroupLeave {
+            group_id,
+            member_id,
+            operator_id: Some(operator_id),
+        } if operator_id != member_id => (
+            *group_id,
+            format!(
+                "{} was removed by {}",
+                name_of(*member_id),
+                name_of(*operator_id)
+            ),
+        ),
+        AppEvent::GroupLeave {
+            group_id,
+            member_id,
+            ..
+        } => (*group_id, format!("{} left", name_of(*member_id))),
+        AppEvent::GroupMute {
+            group_id,
+            target_id: 0,
+            duration,
+            ..
+        } => {
+            let action = if duration.is_zero() {
+                "unmuted"
+            } else {
+                "muted"
+            };
+            (*group_id, format!("Everyone was {}", action))
+        }
+        AppEvent::GroupMute {
+            group_id,
+            target_id,
+            duration,
+            ..
+        } => {
+            let notice = if duration.is_zero() {
+                format!("{} was unmuted", name_of(*target_id))
+            } else {
+                format!(
+                    "{} was muted for {}",
+                    name_of(*target_id),
+                    format_duration(*duration)
+                )
+            };
+            (*group_id, notice)
+        }
+        AppEvent::GroupNameUpdate {
+            group_id,
+            operator_id,
+            name,
+        } => (
+            *group_id,
+            format!(
+                "{} renamed the group to \"{}\"",
+                name_of(*operator_id),
+                name
+            ),
+        ),
+        AppEvent::GroupDisband { group_id, .. } => {
+            (*group_id, "The group was disbanded".to_string())
+        }
+        AppEvent::MemberPermissionChange {
+            group_id,
+            member_id,
+            permission,
+        } => {
+            let role = match permission {
+                GroupMemberPermission::Owner => "the owner",
+                GroupMemberPermission::Administrator => "an administrator",
+                GroupMemberPermission::Member => "a member",
+            };
+            (
+                *group_id,
+                format!("{} is now {}", name_of(*member_id), role),
+            )
+        }
+        _ => return None,
+    };
+
+    Some(notice)
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::{describe_group_event, format_duration};
+    use crate::event::AppEvent;
+
+    fn name_of(id: i64) -> String {
+        match id {
+            1 => "Alice".to_string(),
+            2 => "Bob".to_string(),
+            _ => id.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_secs(1)), "1 second");
+        assert_eq!(format_duration(Duration::from_secs(600)), "10 minutes");
+        assert_eq!(format_duration(Duration::from_secs(3600)), "1 hour");
+        assert_eq!(format_duration(Duration::from_secs(30 * 86400)), "30 days");
+    }
+
+    #[test]
+    fn test_describe_group_event() {
+        let describe = |event| describe_group_event(&event, name_of).unwrap();
+
+        assert_eq!(
+            describe(AppEvent::NewMember {
+                group_id: 10,
+                member_id: 1
+            }),
+            (10, "Alice joined".to_string())
+        );
+        assert_eq!(
+            describe(AppEvent::GroupLeave {
+                group_id: 10,
+                member_id: 2,
+                operator_id: Some(1)
+            })
+            .1,
+            "Bob was removed by Alice"
+        );
+        assert_eq!(
+            describe(AppEvent::GroupMute {
+                group_id: 10,
+                operator_id: 1,
+                target_id: 2,
+                duration: Duration::from_secs(600)
+            })
+            .1,
+            "Bob was muted for 10 minutes"
+        );
+        assert_eq!(
+            describe(AppEvent::GroupMute {
+                group_id: 10,
+                operator_id: 1,
+                target_id: 0,
+                duration: Duration::ZERO
+            })
+            .1,
+            "Everyone was unmuted"
+        );
+    }
+
+    #[test]
+    fn test_describe_other_event() {
+        let event = AppEvent::DeleteFriend { friend_id: 1 };
+        assert!(describe_group_event(&event, name_of).is_none());
+    }
+}
@@ -1,4 +1,5 @@
-use super::Content;
+use super::card::{parse_light_app, parse_rich_msg};
+use super::{parse_forward, Content};
 use ricq::msg::elem::{FingerGuessing, FlashImage, RQElem};
 use ricq::msg::MessageChain;
 
@@ -32,12 +33,20 @@ pub(crate) fn get_contents_from(message_chain: &MessageChain) -> Vec<Content> {
                 ));
             }
             RQElem::LightApp(light_app) => {
-                contents.push(Content::Text("[LIGHT_APP MESSAGE]".to_string()));
-                println!("LightApp: {:#?}", light_app);
+                contents.push(match parse_light_app(&light_app.content) {
+                    Some(card) => Content::Card(card),
+                    None => Content::Text(light_app.content),
+                });
             }
             RQElem::RichMsg(rich_msg) => {
-                contents.push(Content::Text("[RICH MESSAGE]".to_string()));
-                println!("RichMsg: {:#?}", rich_msg);
+                if let Some(forward) = parse_forward(&rich_msg.template1) {
+                    contents.push(Content::Forward(forward));
+                    continue;
+                }
+                contents.push(match parse_rich_msg(&rich_msg.template1) {
+                    Some(card) => Content::Card(card),
+                    None => Content::Text(rich_msg.template1),
+                });
             }
             RQElem::FriendImage(image) => {
                 let content = Content::Image {
@@ -60,8 +69,8 @@ pub(crate) fn get_contents_from(message_chain: &MessageChain) -> Vec<Content> {
                 };
                 contents.push(content);
             }
-            RQElem::VideoFile(_) => {
-                contents.push(Content::Text("[视频文件]".to_string()));
+            RQElem::VideoFile(video_file) => {
+                contents.push(Content::Video(video_file.into()));
             }
             RQElem::Other(_) => {}
         }
@@ -0,0 +1,64 @@
+use ricq::msg::elem::VideoFile;
+use ricq::Client;
+
+use super::Content;
+
+#[derive(Debug, Clone)]
+pub(crate) struct Video {
+    pub name: String,
+    /// In bytes.
+    pub size: i64,
+    /// The md5 of the video in hex, which names the cached file.
+    pub md5: String,
+    pub thumb_url: String,
+    /// The download url is resolved by `resolve_video_urls` after the message is received.
+    pub url: Option<String>,
+    file: VideoFile,
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+impl From<VideoFile> for Video {
+    fn from(file: VideoFile) -> Self {
+        // Thumbnails are uploaded as ordinary images, so they can be fetched by their md5.
+        let thumb_url = format!(
+            "https://gchat.qpic.cn/gchatpic_new/0/0-0-{}/0",
+            to_hex(&file.thumb_md5).to_uppercase()
+        );
+        Video {
+            name: file.name.clone(),
+            size: file.size as i64,
+            md5: to_hex(&file.md5),
+            thumb_url,
+            url: None,
+            file,
+        }
+    }
+}
+
+pub(crate) async fn resolve_video_urls(
+    client: &Client,
+    target: i64,
+    is_group: bool,
+    contents: &mut [Content],
+) {
+    for content in contents.iter_mut() {
+        if let Content::Video(video) = content {
+            let res = if is_group {
+                client
+                    .get_group_short_video_url(target, video.file.clone())
+                    .await
+            } else {
+                client
+                    .get_friend_short_video_url(target, video.file.clone())
+                    .await
+            };
+            match res {
+                Ok(url) => video.url = Some(url),
+                Err(err) => log::warn!("Failed to get video url: {}", err),
+            }
+        }
+    }
+}
@@ -1,4 +1,7 @@
+pub mod audio;
 pub mod avatar;
+pub mod media;
 pub mod message;
+pub mod search;
 
 pub use resource_loader::DirAction;
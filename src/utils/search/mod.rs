@@ -0,0 +1,303 @@
+//! Contact search which understands pinyin: "zs", "zhangsan" and "zhangs"
+//! all find "张三", and digits also match the uin.
+
+use std::collections::HashMap;
+
+use pinyin::ToPinyin;
+
+/// A character of a name, with its pinyin if it is a Chinese character.
+#[derive(Debug)]
+struct Token {
+    text: String,
+    pinyin: Option<&'static str>,
+}
+
+impl Token {
+    fn new(c: char) -> Self {
+        Token {
+            text: c.to_lowercase().collect(),
+            pinyin: c.to_pinyin().map(|pinyin| pinyin.plain()),
+        }
+    }
+
+    fn is_whitespace(&self) -> bool {
+        self.text.chars().all(char::is_whitespace)
+    }
+
+    /// The lengths of the prefixes of `rest` the token can match, the
+    /// longest first. A pinyin is matched fully, by its initial, or
+    /// partially at the end of the keyword, which is still being typed.
+    fn matches(&self, rest: &str) -> Vec<usize> {
+        let mut lengths = Vec::new();
+        if rest.starts_with(&self.text) {
+            lengths.push(self.text.len());
+        }
+        if let Some(pinyin) = self.pinyin {
+            if rest.starts_with(pinyin) {
+                lengths.push(pinyin.len());
+            } else if pinyin.starts_with(rest) {
+                lengths.push(rest.len());
+            }
+            if rest.starts_with(&pinyin[..1]) {
+                lengths.push(1);
+            }
+        }
+        lengths.sort_unstable_by(|a, b| b.cmp(a));
+        lengths.dedup();
+        lengths
+    }
+}
+
+type Memo = HashMap<(usize, usize), Option<Vec<usize>>>;
+
+/// Match `keyword[offset..]` against the tokens from `index`, returning the
+/// positions of the matched tokens. Unless `gaps` is set, the matched
+/// tokens have to be consecutive, apart from whitespaces.
+fn walk(
+    tokens: &[Token],
+    keyword: &str,
+    index: usize,
+    offset: usize,
+    gaps: bool,
+    memo: &mut Memo,
+) -> Option<Vec<usize>> {
+    if offset == keyword.len() {
+        return Some(Vec::new());
+    }
+    if index == tokens.len() {
+        return None;
+    }
+    if let Some(result) = memo.get(&(index, offset)) {
+        return result.clone();
+    }
+
+    let token = &tokens[index];
+    let mut result = None;
+    for length in token.matches(&keyword[offset..]) {
+        if let Some(mut positions) = walk(tokens, keyword, index + 1, offset + length, gaps, memo) {
+            positions.insert(0, index);
+            result = Some(positions);
+            break;
+        }
+    }
+    if result.is_none() && (gaps || token.is_whitespace()) {
+        result = walk(tokens, keyword, index + 1, offset, gaps, memo);
+    }
+
+    memo.insert((index, offset), result.clone());
+    result
+}
+
+/// The score of the best match of `keyword` in the name, and the positions
+/// of the matched characters.
+fn match_name(tokens: &[Token], keyword: &str) -> Option<(i32, Vec<usize>)> {
+    let mut memo = Memo::new();
+    for start in 0..tokens.len() {
+        if let Some(positions) = walk(tokens, keyword, start, 0, false, &mut memo) {
+            let score = match (start, positions.last()) {
+                (0, Some(&last)) if last + 1 == tokens.len() => 100,
+                (0, _) => 80,
+                _ => 60,
+            };
+            return Some((score, positions));
+        }
+    }
+
+    let mut memo = Memo::new();
+    walk(tokens, keyword, 0, 0, true, &mut memo).map(|positions| (40, positions))
+}
+
+fn match_uin(uin: &str, keyword: &str) -> Option<(i32, Vec<usize>)> {
+    let start = uin.find(keyword)?;
+    let score = if uin == keyword {
+        100
+    } else if start == 0 {
+        80
+    } else {
+        60
+    };
+    Some((score, (start..start + keyword.len()).collect()))
+}
+
+#[derive(Debug)]
+struct Entry<T> {
+    item: T,
+    uin: String,
+    names: Vec<(String, Vec<Token>)>,
+}
+
+/// A search result, with the matched characters of each name and the uin.
+#[derive(Debug, Clone)]
+pub struct SearchHit<T> {
+    pub item: T,
+    pub score: i32,
+    /// For each name, in the order they are inserted.
+    pub names: Vec<Vec<usize>>,
+    pub uin: Vec<usize>,
+}
+
+#[derive(Debug)]
+pub struct SearchIndex<T> {
+    entries: Vec<Entry<T>>,
+}
+
+impl<T> Default for SearchIndex<T> {
+    fn default() -> Self {
+        SearchIndex {
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl<T: Clone> SearchIndex<T> {
+    /// Add `item`, to be found by its uin or any of the `names`.
+    pub fn insert(&mut self, item: T, uin: i64, names: &[&str]) {
+        self.entries.push(Entry {
+            item,
+            uin: uin.to_string(),
+            names: names
+                .iter()
+                .map(|name| (name.to_string(), name.chars().map(Token::new).collect()))
+                .collect(),
+        });
+    }
+
+    /// The items matching `keyword`, the best matches first. Shorter names
+    /// come first among equally good matches.
+    pub fn search(&self, keyword: &str) -> Vec<SearchHit<T>> {
+        let keyword: String = keyword
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .flat_map(char::to_lowercase)
+            .collect();
+        if keyword.is_empty() {
+            return Vec::new();
+        }
+
+        let mut hits: Vec<(usize, SearchHit<T>)> = Vec::new();
+        for entry in self.entries.iter() {
+            let mut score = 0;
+            let mut shortest = usize::MAX;
+            let mut names = Vec::new();
+            for (name, tokens) in entry.names.iter() {
+                match match_name(tokens, &keyword) {
+                    Some((name_score, positions)) => {
+                        score = score.max(name_score);
+                        shortest = shortest.min(name.chars().count());
+                        names.push(positions);
+                    }
+                    None => names.push(Vec::new()),
+                }
+            }
+            let uin = match match_uin(&entry.uin, &keyword) {
+                Some((uin_score, positions)) => {
+                    score = score.max(uin_score);
+                    positions
+                }
+                None => Vec::new(),
+            };
+
+            if score > 0 {
+                let hit = SearchHit {
+                    item: entry.item.clone(),
+                    score,
+                    names,
+                    uin,
+                };
+                hits.push((shortest, hit));
+            }
+        }
+
+        hits.sort_by(|(a_len, a), (b_len, b)| b.score.cmp(&a.score).then(a_len.cmp(b_len)));
+        hits.into_iter().map(|(_, hit)| hit).collect()
+    }
+}
+
+fn escape(c: char, markup: &mut String) {
+    match c {
+        '&' => markup.push_str("&amp;"),
+        '<' => markup.push_str("&lt;"),
+        '>' => markup.push_str("&gt;"),
+        '\'' => markup.push_str("&#39;"),
+        '"' => markup.push_str("&quot;"),
+        c => markup.push(c),
+    }
+}
+
+/// Pango markup of `text` with the characters at `positions` in bold.
+pub fn highlight(text: &str, positions: &[usize]) -> String {
+    let mut markup = String::new();
+    let mut bold = false;
+    for (index, c) in text.chars().enumerate() {
+        let matched = positions.contains(&index);
+        if matched != bold {
+            markup.push_str(if matched { "<b>" } else { "</b>" });
+            bold = matched;
+        }
+        escape(c, &mut markup);
+    }
+    if bold {
+        markup.push_str("</b>");
+    }
+    markup
+}
+
+#[cfg(test)]
+mod test {
+    use super::{highlight, SearchIndex};
+
+    fn index() -> SearchIndex<i64> {
+        let mut index = SearchIndex::default();
+        index.insert(1, 10001, &["张三", "Zhang San"]);
+        index.insert(2, 10002, &["李四", "lisi"]);
+        index.insert(3, 20003, &["张三丰"]);
+        index
+    }
+
+    fn search(keyword: &str) -> Vec<i64> {
+        index()
+            .search(keyword)
+            .into_iter()
+            .map(|hit| hit.item)
+            .collect()
+    }
+
+    #[test]
+    fn test_pinyin() {
+        assert_eq!(search("zs"), vec![1, 3]);
+        assert_eq!(search("zhangsan"), vec![1, 3]);
+        assert_eq!(search("zhangs"), vec![1, 3]);
+        assert_eq!(search("zsf"), vec![3]);
+        assert_eq!(search("张三"), vec![1, 3]);
+        assert_eq!(search("Zhang San"), vec![1, 3]);
+    }
+
+    #[test]
+    fn test_fuzzy() {
+        // Initials which are not consecutive.
+        assert_eq!(search("zf"), vec![3]);
+        assert!(search("xyz").is_empty());
+    }
+
+    #[test]
+    fn test_uin() {
+        assert_eq!(search("10002"), vec![2]);
+        assert_eq!(search("1000"), vec![1, 2]);
+        assert_eq!(search("0003"), vec![3]);
+    }
+
+    #[test]
+    fn test_positions() {
+        let hits = index().search("sf");
+        assert_eq!(hits[0].item, 3);
+        assert_eq!(hits[0].names, vec![vec![1, 2]]);
+        assert!(hits[0].uin.is_empty());
+    }
+
+    #[test]
+    fn test_highlight() {
+        assert_eq!(highlight("张三丰", &[1, 2]), "张<b>三丰</b>");
+        assert_eq!(highlight("a<b", &[0]), "<b>a</b>&lt;b");
+        assert_eq!(highlight("abc", &[]), "abc");
+    }
+}